@@ -0,0 +1,106 @@
+//! Modbus ASCII client (master) specific functions.
+use super::*;
+
+/// Encode an ASCII request.
+pub fn encode_request(adu: RequestAdu, buf: &mut [u8]) -> Result<usize> {
+    let RequestAdu { hdr, pdu } = adu;
+    encode_frame(hdr.slave, &pdu, buf)
+}
+
+/// Decode an ASCII response.
+///
+/// The binary content of the frame is written to `target`.
+pub fn decode_response<'t>(buf: &[u8], target: &'t mut [u8]) -> Result<Option<ResponseAdu<'t>>> {
+    decode(DecoderType::Response, buf, DEFAULT_DELIMITER, target).and_then(|frame| {
+        if let Some((DecodedFrame { slave, pdu }, _frame_pos)) = frame {
+            let hdr = Header { slave };
+            ResponsePdu::try_from(pdu).map(|pdu| Some(ResponseAdu { hdr, pdu }))
+        } else {
+            Ok(None)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_read_holding_registers_request() {
+        let adu = RequestAdu {
+            hdr: Header { slave: 0x01 },
+            pdu: RequestPdu(Request::ReadHoldingRegisters(0x082B, 2)),
+        };
+        let buf = &mut [0; 100];
+        let len = encode_request(adu, buf).unwrap();
+        assert_eq!(len, 17);
+        assert_eq!(&buf[..len], b":0103082B0002C7\r\n");
+    }
+
+    #[test]
+    fn encode_request_with_too_small_buffer() {
+        let adu = RequestAdu {
+            hdr: Header { slave: 0x01 },
+            pdu: RequestPdu(Request::ReadHoldingRegisters(0x082B, 2)),
+        };
+        assert!(encode_request(adu, &mut [0; 16]).is_err());
+    }
+
+    #[test]
+    fn decode_empty_response() {
+        assert!(decode_response(&[], &mut [0; 16]).unwrap().is_none());
+    }
+
+    #[test]
+    fn decode_partly_received_response() {
+        assert!(decode_response(b":010304890242C7", &mut [0; 16])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn decode_read_holding_registers_response() {
+        let target = &mut [0; 16];
+        let ResponseAdu { hdr, pdu } = decode_response(b":010304890242C764\r\n", target)
+            .unwrap()
+            .unwrap();
+        assert_eq!(hdr.slave, 0x01);
+        let ResponsePdu(rsp) = pdu;
+        if let Response::ReadHoldingRegisters(data) = rsp.unwrap() {
+            assert_eq!(data.get(0), Some(0x8902));
+            assert_eq!(data.get(1), Some(0x42C7));
+            assert_eq!(data.get(2), None);
+        } else {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn decode_exception_response() {
+        let target = &mut [0; 16];
+        let ResponseAdu { hdr, pdu } = decode_response(b":12830269\r\n", target).unwrap().unwrap();
+        assert_eq!(hdr.slave, 0x12);
+        assert_eq!(
+            pdu,
+            ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::ReadHoldingRegisters,
+                exception: Exception::IllegalDataAddress,
+            }))
+        );
+    }
+
+    #[test]
+    fn encode_response_decode_response_roundtrip() {
+        let rsp_adu = ResponseAdu {
+            hdr: Header { slave: 0x05 },
+            pdu: ResponsePdu(Ok(Response::WriteSingleRegister(0x2222, 0xABCD))),
+        };
+        let buf = &mut [0; 100];
+        let len = server::encode_response(rsp_adu, buf).unwrap();
+        let target = &mut [0; 16];
+        assert_eq!(
+            decode_response(&buf[..len], target).unwrap().unwrap(),
+            rsp_adu
+        );
+    }
+}
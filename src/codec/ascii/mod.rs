@@ -0,0 +1,538 @@
+//! Modbus ASCII
+
+use super::*;
+
+pub mod client;
+pub mod server;
+pub use super::rtu::{DecodedFrame, FrameLocation};
+pub use crate::frame::rtu::*;
+
+// [MODBUS over Serial Line Specification and Implementation Guide V1.02](http://modbus.org/docs/Modbus_over_serial_line_V1_02.pdf), page 17
+// "The maximum size of a MODBUS ASCII frame is 513 characters."
+const MAX_FRAME_LEN: usize = 513;
+
+/// The character that starts a frame.
+const START: u8 = b':';
+
+/// The first character of the end of a frame.
+const CR: u8 = b'\r';
+
+/// The default second character of the end of a frame.
+///
+/// It can be changed with the "Change ASCII Input Delimiter"
+/// diagnostics sub-function.
+pub const DEFAULT_DELIMITER: u8 = b'\n';
+
+/// Decode ASCII PDU frames from a buffer.
+///
+/// The binary content of the frame is written to `target`.
+pub fn decode<'t>(
+    decoder_type: DecoderType,
+    buf: &[u8],
+    delimiter: u8,
+    target: &'t mut [u8],
+) -> Result<Option<(DecodedFrame<'t>, FrameLocation)>> {
+    use DecoderType::*;
+    let mut drop_cnt = 0;
+
+    let (location, adu_len) = loop {
+        let raw_frame = &buf[drop_cnt..];
+        let start = match raw_frame.iter().position(|b| *b == START) {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+        if start > 0 {
+            warn!(
+                "Dropping {} byte(s) in front of the frame: {:X?}",
+                start,
+                &raw_frame[..start]
+            );
+            drop_cnt += start;
+        }
+        let raw_frame = &buf[drop_cnt..];
+        let frame_len = frame_len(raw_frame, delimiter);
+        let next_start = raw_frame[1..]
+            .iter()
+            .position(|b| *b == START)
+            .map(|pos| pos + 1);
+
+        let frame_len = match (frame_len, next_start) {
+            (_, Some(next_start)) if frame_len.is_none_or(|len| next_start < len) => {
+                // Resync on a stray start character
+                warn!("Dropping incomplete frame: {:X?}", &raw_frame[..next_start]);
+                drop_cnt += next_start;
+                continue;
+            }
+            (Some(frame_len), _) => frame_len,
+            (None, _) => {
+                if raw_frame.len() >= MAX_FRAME_LEN {
+                    error!(
+                        "Giving up to decode frame after receiving {} byte(s) without an end",
+                        raw_frame.len()
+                    );
+                    return Err(Error::BufferSize);
+                }
+                // Incomplete frame
+                return Ok(None);
+            }
+        };
+        if target.len() < (frame_len - 3) / 2 {
+            return Err(Error::BufferSize);
+        }
+        let res = extract_adu(&raw_frame[..frame_len], target).and_then(|adu_len| {
+            let adu_buf = &target[..adu_len];
+            let pdu_len = match decoder_type {
+                Request => rtu::request_pdu_len(adu_buf),
+                Response => rtu::response_pdu_len(adu_buf),
+            }?;
+            match pdu_len {
+                Some(pdu_len) if pdu_len + 1 == adu_len => Ok(adu_len),
+                Some(pdu_len) => Err(Error::LengthMismatch(pdu_len + 1, adu_len)),
+                None => Err(Error::BufferSize),
+            }
+        });
+        match res {
+            Ok(adu_len) => {
+                break (
+                    FrameLocation {
+                        start: drop_cnt,
+                        size: frame_len,
+                    },
+                    adu_len,
+                );
+            }
+            Err(err) => {
+                let pdu_type = match decoder_type {
+                    Request => "request",
+                    Response => "response",
+                };
+                if drop_cnt + frame_len >= MAX_FRAME_LEN {
+                    error!(
+                        "Giving up to decode frame after dropping {} byte(s): {:X?}",
+                        drop_cnt,
+                        &buf[0..drop_cnt]
+                    );
+                    return Err(err);
+                }
+                warn!("Failed to decode {} frame: {}", pdu_type, err);
+                drop_cnt += frame_len;
+            }
+        }
+    };
+    let (slave_id, pdu_data) = target[..adu_len].split_at(1);
+    Ok(Some((
+        DecodedFrame {
+            slave: slave_id[0],
+            pdu: pdu_data,
+        },
+        location,
+    )))
+}
+
+/// Extract a PDU frame out of a buffer that starts with a complete frame.
+///
+/// The binary content of the frame is written to `target`.
+pub fn extract_frame<'t>(
+    buf: &[u8],
+    delimiter: u8,
+    target: &'t mut [u8],
+) -> Result<Option<DecodedFrame<'t>>> {
+    if buf.first() != Some(&START) {
+        return Err(Error::BufferSize);
+    }
+    if let Some(frame_len) = frame_len(buf, delimiter) {
+        let adu_len = extract_adu(&buf[..frame_len], target)?;
+        let (slave_id, pdu_data) = target[..adu_len].split_at(1);
+        return Ok(Some(DecodedFrame {
+            slave: slave_id[0],
+            pdu: pdu_data,
+        }));
+    }
+    // Incomplete frame
+    Ok(None)
+}
+
+/// Get the number of characters of a frame including the start and
+/// end characters.
+///
+/// It returns `None` if the end of the frame has not been received yet.
+pub fn frame_len(buf: &[u8], delimiter: u8) -> Option<usize> {
+    buf.windows(2)
+        .position(|w| w[0] == CR && w[1] == delimiter)
+        .map(|pos| pos + 2)
+}
+
+/// Calculate the LRC (Longitudinal Redundancy Check) sum.
+pub fn lrc(data: &[u8]) -> u8 {
+    data.iter()
+        .fold(0u8, |lrc, x| lrc.wrapping_add(*x))
+        .wrapping_neg()
+}
+
+/// Decode the hex characters of a complete frame into `target`
+/// and verify the LRC.
+///
+/// It returns the length of the ADU without the LRC.
+fn extract_adu(frame: &[u8], target: &mut [u8]) -> Result<usize> {
+    let hex = &frame[1..frame.len() - 2];
+    // At least a slave ID, a function code and the LRC
+    if hex.len() < 6 || !hex.len().is_multiple_of(2) {
+        return Err(Error::BufferSize);
+    }
+    let len = hex.len() / 2;
+    if target.len() < len {
+        return Err(Error::BufferSize);
+    }
+    for (i, pair) in hex.chunks(2).enumerate() {
+        target[i] = (hex_digit(pair[0])? << 4) | hex_digit(pair[1])?;
+    }
+    let adu_len = len - 1;
+    let expected_lrc = target[adu_len];
+    let actual_lrc = lrc(&target[..adu_len]);
+    if expected_lrc != actual_lrc {
+        return Err(Error::Lrc(expected_lrc, actual_lrc));
+    }
+    Ok(adu_len)
+}
+
+fn hex_digit(c: u8) -> Result<u8> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        _ => Err(Error::HexDigit(c)),
+    }
+}
+
+const fn hex_char(nibble: u8) -> u8 {
+    match nibble {
+        0..=9 => b'0' + nibble,
+        _ => b'A' + nibble - 10,
+    }
+}
+
+/// Encode a complete ASCII frame.
+fn encode_frame(slave: SlaveId, pdu: &impl Encode, buf: &mut [u8]) -> Result<usize> {
+    if buf.len() < 2 {
+        return Err(Error::BufferSize);
+    }
+    let pdu_len = pdu.encode(&mut buf[1..])?;
+    // slave ID + PDU + LRC
+    let len = pdu_len + 2;
+    let frame_len = 1 + len * 2 + 2;
+    if buf.len() < frame_len {
+        return Err(Error::BufferSize);
+    }
+    buf[0] = slave;
+    buf[len - 1] = lrc(&buf[..len - 1]);
+    // Expand the binary data in place, starting with the last byte
+    // to not overwrite bytes that have not been expanded yet.
+    for i in (0..len).rev() {
+        let b = buf[i];
+        buf[1 + i * 2] = hex_char(b >> 4);
+        buf[2 + i * 2] = hex_char(b & 0x0F);
+    }
+    buf[0] = START;
+    buf[frame_len - 2] = CR;
+    buf[frame_len - 1] = DEFAULT_DELIMITER;
+    Ok(frame_len)
+}
+
+/// The ASCII transport.
+///
+/// It keeps the current delimiter and the binary content of the last frame.
+#[derive(Debug, Clone)]
+pub struct Ascii {
+    delimiter: u8,
+    target: [u8; MAX_FRAME_LEN / 2],
+}
+
+impl Transport for Ascii {
+    type Header = Header;
+    const INIT: Self = Self {
+        delimiter: DEFAULT_DELIMITER,
+        target: [0; MAX_FRAME_LEN / 2],
+    };
+    const MAX_FRAME_LEN: usize = MAX_FRAME_LEN;
+    const DROP_ON_ERR: usize = 1;
+
+    fn locate(&mut self, decoder_type: DecoderType, buf: &[u8]) -> Result<Option<(usize, usize)>> {
+        // A complete frame with an invalid LRC is located, so that the
+        // error is reported by `extract` instead of being skipped.
+        if let Some(start) = buf.iter().position(|b| *b == START) {
+            let raw_frame = &buf[start..];
+            if let Some(frame_len) = frame_len(raw_frame, self.delimiter) {
+                let frame = &raw_frame[..frame_len];
+                if !frame[1..].contains(&START)
+                    && matches!(extract_adu(frame, &mut self.target), Err(Error::Lrc(_, _)))
+                {
+                    return Ok(Some((start, frame_len)));
+                }
+            }
+        }
+        decode(decoder_type, buf, self.delimiter, &mut self.target)
+            .map(|frame| frame.map(|(_, loc)| (loc.start, loc.size)))
+    }
+    fn extract<'a>(&'a mut self, frame: &'a [u8]) -> Result<Option<(Header, &'a [u8])>> {
+        extract_frame(frame, self.delimiter, &mut self.target)
+            .map(|frame| frame.map(|DecodedFrame { slave, pdu }| (Header { slave }, pdu)))
+    }
+    fn encode_request(adu: RequestAdu, buf: &mut [u8]) -> Result<usize> {
+        client::encode_request(adu, buf)
+    }
+    fn encode_response(adu: ResponseAdu, buf: &mut [u8]) -> Result<usize> {
+        server::encode_response(adu, buf)
+    }
+}
+
+/// An incremental decoder for ASCII frames with a fixed capacity of `N` bytes.
+///
+/// `N` must be at least 513 bytes, the maximum size of an ASCII frame.
+pub type FrameDecoder<const N: usize> = super::FrameDecoder<Ascii, N>;
+
+impl<const N: usize> FrameDecoder<N> {
+    /// Change the second character of the end of a frame.
+    pub fn set_delimiter(&mut self, delimiter: u8) {
+        self.transport_mut().delimiter = delimiter;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calc_lrc() {
+        let msg = &[0x01, 0x03, 0x08, 0x2B, 0x00, 0x02];
+        assert_eq!(lrc(msg), 0xC7);
+
+        let msg = &[0xF7, 0x03, 0x13, 0x89, 0x00, 0x0A];
+        assert_eq!(lrc(msg), 0x60);
+
+        assert_eq!(lrc(&[]), 0x00);
+    }
+
+    #[test]
+    fn test_frame_len() {
+        assert_eq!(frame_len(b":0103082B0002C7", b'\n'), None);
+        assert_eq!(frame_len(b":0103082B0002C7\r", b'\n'), None);
+        assert_eq!(frame_len(b":0103082B0002C7\r\n", b'\n'), Some(17));
+        assert_eq!(frame_len(b":0103082B0002C7\r\n:01", b'\n'), Some(17));
+        assert_eq!(frame_len(b":0103082B0002C7\r\n", b'!'), None);
+        assert_eq!(frame_len(b":0103082B0002C7\r!", b'!'), Some(17));
+    }
+
+    #[test]
+    fn test_encode_frame() {
+        let pdu = RequestPdu(Request::ReadHoldingRegisters(0x082B, 2));
+        let buf = &mut [0; 17];
+        assert_eq!(encode_frame(0x01, &pdu, buf).unwrap(), 17);
+        assert_eq!(buf, b":0103082B0002C7\r\n");
+        assert!(encode_frame(0x01, &pdu, &mut [0; 16]).is_err());
+    }
+
+    mod frame_decoder {
+
+        use super::*;
+
+        #[test]
+        fn extract_partly_received_ascii_frame() {
+            let target = &mut [0; 16];
+            let res = extract_frame(b":0103082B0002C7\r", b'\n', target).unwrap();
+            assert!(res.is_none());
+        }
+
+        #[test]
+        fn extract_usual_ascii_frame() {
+            let target = &mut [0; 16];
+            let frame = extract_frame(b":0103082B0002C7\r\n", b'\n', target)
+                .unwrap()
+                .unwrap();
+            assert_eq!(frame.slave, 0x01);
+            assert_eq!(frame.pdu, &[0x03, 0x08, 0x2B, 0x00, 0x02]);
+        }
+
+        #[test]
+        fn extract_lowercase_ascii_frame() {
+            let target = &mut [0; 16];
+            let frame = extract_frame(b":0103082b0002c7\r\n", b'\n', target)
+                .unwrap()
+                .unwrap();
+            assert_eq!(frame.pdu, &[0x03, 0x08, 0x2B, 0x00, 0x02]);
+        }
+
+        #[test]
+        fn extract_ascii_frame_with_invalid_lrc() {
+            let target = &mut [0; 16];
+            let err = extract_frame(b":0103082B0002C8\r\n", b'\n', target)
+                .err()
+                .unwrap();
+            assert_eq!(err, Error::Lrc(0xC8, 0xC7));
+        }
+
+        #[test]
+        fn extract_ascii_frame_with_invalid_characters() {
+            let target = &mut [0; 16];
+            let err = extract_frame(b":0103082X0002C7\r\n", b'\n', target)
+                .err()
+                .unwrap();
+            assert_eq!(err, Error::HexDigit(b'X'));
+            let err = extract_frame(b":0103082B0002C\r\n", b'\n', target)
+                .err()
+                .unwrap();
+            assert_eq!(err, Error::BufferSize);
+        }
+
+        #[test]
+        fn extract_ascii_frame_with_custom_delimiter() {
+            let target = &mut [0; 16];
+            let frame = extract_frame(b":0103082B0002C7\r!", b'!', target)
+                .unwrap()
+                .unwrap();
+            assert_eq!(frame.slave, 0x01);
+        }
+
+        #[test]
+        fn decode_ascii_request_frame() {
+            let target = &mut [0; 16];
+            let (frame, location) = decode(
+                DecoderType::Request,
+                b":0103082B0002C7\r\n",
+                DEFAULT_DELIMITER,
+                target,
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(frame.slave, 0x01);
+            assert_eq!(frame.pdu, &[0x03, 0x08, 0x2B, 0x00, 0x02]);
+            assert_eq!(location, FrameLocation { start: 0, size: 17 });
+        }
+
+        #[test]
+        fn decode_ascii_frame_with_leading_garbage() {
+            let target = &mut [0; 16];
+            let (frame, location) = decode(
+                DecoderType::Request,
+                b"\x00\x42\r\n:0103082B0002C7\r\n",
+                DEFAULT_DELIMITER,
+                target,
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(frame.slave, 0x01);
+            assert_eq!(location, FrameLocation { start: 4, size: 17 });
+        }
+
+        #[test]
+        fn decode_ascii_frame_after_stray_start() {
+            let target = &mut [0; 16];
+            let (frame, location) = decode(
+                DecoderType::Request,
+                b":0103:0103082B0002C7\r\n",
+                DEFAULT_DELIMITER,
+                target,
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(frame.slave, 0x01);
+            assert_eq!(location, FrameLocation { start: 5, size: 17 });
+        }
+
+        #[test]
+        fn decode_ascii_frame_after_corrupted_frame() {
+            let target = &mut [0; 16];
+            let (frame, location) = decode(
+                DecoderType::Request,
+                b":0103082B0002C8\r\n:0103082B0002C7\r\n",
+                DEFAULT_DELIMITER,
+                target,
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(frame.slave, 0x01);
+            assert_eq!(
+                location,
+                FrameLocation {
+                    start: 17,
+                    size: 17
+                }
+            );
+        }
+
+        #[test]
+        fn decode_ascii_frame_with_length_mismatch() {
+            let target = &mut [0; 16];
+            let res = decode(
+                DecoderType::Request,
+                b":0103082BC9\r\n",
+                DEFAULT_DELIMITER,
+                target,
+            )
+            .unwrap();
+            assert!(res.is_none());
+        }
+
+        #[test]
+        fn decode_incomplete_ascii_frame() {
+            let target = &mut [0; 16];
+            let res = decode(
+                DecoderType::Request,
+                b":0103082B00",
+                DEFAULT_DELIMITER,
+                target,
+            )
+            .unwrap();
+            assert!(res.is_none());
+        }
+
+        #[test]
+        fn decode_ascii_frame_without_end() {
+            let target = &mut [0; 256];
+            let mut buf = [b'0'; MAX_FRAME_LEN];
+            buf[0] = START;
+            let err = decode(DecoderType::Request, &buf, DEFAULT_DELIMITER, target)
+                .err()
+                .unwrap();
+            assert_eq!(err, Error::BufferSize);
+        }
+    }
+
+    #[test]
+    fn decode_frames_pushed_into_frame_decoder() {
+        let mut decoder = FrameDecoder::<513>::new();
+        decoder.push(b"\x00:01:0103082B00");
+        assert!(decoder.next_request().unwrap().is_none());
+        decoder.push(b"02C7\r\n");
+        let adu = decoder.next_request().unwrap().unwrap();
+        assert_eq!(adu.hdr.slave, 0x01);
+        assert_eq!(
+            adu.pdu,
+            RequestPdu(Request::ReadHoldingRegisters(0x082B, 2))
+        );
+        assert_eq!(decoder.dropped(), 4);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn report_frame_with_invalid_lrc() {
+        let mut decoder = FrameDecoder::<513>::new();
+        decoder.push(b":0103082B0002C8\r\n:0103082B0002C7\r\n");
+        assert_eq!(
+            decoder.next_request().err().unwrap(),
+            RequestError::Frame(Error::Lrc(0xC8, 0xC7))
+        );
+        let adu = decoder.next_request().unwrap().unwrap();
+        assert_eq!(adu.hdr.slave, 0x01);
+        assert_eq!(decoder.dropped(), 17);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn frame_decoder_with_custom_delimiter() {
+        let mut decoder = FrameDecoder::<513>::new();
+        decoder.set_delimiter(b'!');
+        decoder.push(b":12062222ABCD2C\r!");
+        let adu = decoder.next_response().unwrap().unwrap();
+        assert_eq!(adu.hdr.slave, 0x12);
+    }
+}
@@ -0,0 +1,90 @@
+//! Modbus ASCII server (slave) specific functions.
+use super::*;
+
+pub use crate::codec::dispatch;
+
+/// Decode an ASCII request.
+///
+/// The binary content of the frame is written to `target`.
+pub fn decode_request<'t>(
+    buf: &[u8],
+    target: &'t mut [u8],
+) -> core::result::Result<Option<RequestAdu<'t>>, RequestError<Header>> {
+    decode_request_with_delimiter(buf, DEFAULT_DELIMITER, target)
+}
+
+/// Decode an ASCII request that ends with a custom delimiter.
+///
+/// The binary content of the frame is written to `target`.
+pub fn decode_request_with_delimiter<'t>(
+    buf: &[u8],
+    delimiter: u8,
+    target: &'t mut [u8],
+) -> core::result::Result<Option<RequestAdu<'t>>, RequestError<Header>> {
+    let (DecodedFrame { slave, pdu }, _frame_pos) =
+        match decode(DecoderType::Request, buf, delimiter, target)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+    let hdr = Header { slave };
+    let pdu = decode_request_pdu(hdr, pdu)?;
+    Ok(Some(RequestAdu { hdr, pdu }))
+}
+
+/// Encode an ASCII response.
+pub fn encode_response(adu: ResponseAdu, buf: &mut [u8]) -> Result<usize> {
+    let ResponseAdu { hdr, pdu } = adu;
+    encode_frame(hdr.slave, &pdu, buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_empty_request() {
+        assert!(decode_request(&[], &mut [0; 16]).unwrap().is_none());
+    }
+
+    #[test]
+    fn decode_partly_received_request() {
+        assert!(decode_request(b":1216", &mut [0; 16]).unwrap().is_none());
+    }
+
+    #[test]
+    fn decode_write_single_register_request() {
+        let target = &mut [0; 16];
+        let adu = decode_request(b":120622220ABCD2C\r\n", target);
+        assert!(adu.unwrap().is_none());
+        let adu = decode_request(b":12062222ABCD2C\r\n", target)
+            .unwrap()
+            .unwrap();
+        let RequestAdu { hdr, pdu } = adu;
+        let RequestPdu(pdu) = pdu;
+        assert_eq!(hdr.slave, 0x12);
+        assert_eq!(FnCode::from(pdu), FnCode::WriteSingleRegister);
+    }
+
+    #[test]
+    fn decode_request_with_custom_delimiter() {
+        let target = &mut [0; 16];
+        let req = decode_request_with_delimiter(b":12062222ABCD2C\r\n", b'!', target).unwrap();
+        assert!(req.is_none());
+        let adu = decode_request_with_delimiter(b":12062222ABCD2C\r!", b'!', target)
+            .unwrap()
+            .unwrap();
+        assert_eq!(adu.hdr.slave, 0x12);
+    }
+
+    #[test]
+    fn encode_write_single_register_response() {
+        let adu = ResponseAdu {
+            hdr: Header { slave: 0x12 },
+            pdu: ResponsePdu(Ok(Response::WriteSingleRegister(0x2222, 0xABCD))),
+        };
+        let buf = &mut [0; 100];
+        let len = encode_response(adu, buf).unwrap();
+        assert_eq!(len, 17);
+        assert_eq!(&buf[..len], b":12062222ABCD2C\r\n");
+    }
+}
@@ -0,0 +1,183 @@
+//! Fixed capacity receive buffer shared by the frame decoders.
+use super::*;
+
+/// A fixed capacity buffer that keeps track of consumed and dropped bytes.
+///
+/// Decoded frames are borrowed from the buffer, so the buffered bytes are
+/// kept contiguous instead of wrapping around like in a ring buffer.
+/// Consumed and dropped bytes only advance the start of the buffered bytes.
+/// The remaining bytes are moved to the front when a push needs the space
+/// at the end, instead of every time a frame is consumed.
+#[derive(Debug, Clone)]
+pub(crate) struct FrameBuffer<const N: usize> {
+    buf: [u8; N],
+    start: usize,
+    end: usize,
+    dropped: usize,
+}
+
+impl<const N: usize> FrameBuffer<N> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            start: 0,
+            end: 0,
+            dropped: 0,
+        }
+    }
+    /// Append bytes and return the number of bytes that fitted.
+    pub(crate) fn push(&mut self, bytes: &[u8]) -> usize {
+        if N - self.end < bytes.len() {
+            self.compact();
+        }
+        let cnt = bytes.len().min(N - self.end);
+        self.buf[self.end..self.end + cnt].copy_from_slice(&bytes[..cnt]);
+        self.end += cnt;
+        cnt
+    }
+    /// Number of bytes that are waiting to be decoded.
+    pub(crate) const fn len(&self) -> usize {
+        self.end - self.start
+    }
+    /// Total number of bytes that have been dropped.
+    pub(crate) const fn dropped(&self) -> usize {
+        self.dropped
+    }
+    pub(crate) fn clear(&mut self) {
+        self.start = 0;
+        self.end = 0;
+    }
+    /// Search the next frame.
+    ///
+    /// `locate` returns the start and size of the first frame within
+    /// the buffered bytes. Bytes in front of the frame are dropped.
+    /// If `locate` fails or the buffer is full without containing
+    /// a complete frame, up to `drop_on_err` bytes are dropped.
+    pub(crate) fn next_frame<F>(
+        &mut self,
+        drop_on_err: usize,
+        mut locate: F,
+    ) -> Result<Option<&[u8]>>
+    where
+        F: FnMut(&[u8]) -> Result<Option<(usize, usize)>>,
+    {
+        let (start, size) = match locate(&self.buf[self.start..self.end]) {
+            Ok(Some(location)) => location,
+            Ok(None) if self.len() == N => {
+                error!("Dropping a frame that does not fit into {} byte(s)", N);
+                self.drop_bytes(drop_on_err.max(1));
+                return Err(Error::BufferSize);
+            }
+            Ok(None) => return Ok(None),
+            Err(err) => {
+                self.drop_bytes(drop_on_err.max(1));
+                return Err(err);
+            }
+        };
+        self.drop_bytes(start);
+        let frame_start = self.start;
+        // The frame stays untouched until the next push.
+        self.start += size;
+        Ok(Some(&self.buf[frame_start..frame_start + size]))
+    }
+
+    fn drop_bytes(&mut self, cnt: usize) {
+        let cnt = cnt.min(self.len());
+        self.dropped += cnt;
+        self.start += cnt;
+    }
+
+    fn compact(&mut self) {
+        self.buf.copy_within(self.start..self.end, 0);
+        self.end -= self.start;
+        self.start = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locate_0xff(buf: &[u8]) -> Result<Option<(usize, usize)>> {
+        match buf.iter().position(|b| *b == 0xFF) {
+            Some(start) if start + 2 <= buf.len() => Ok(Some((start, 2))),
+            Some(_) => Ok(None),
+            None if buf.len() > 3 => Err(Error::BufferSize),
+            None => Ok(None),
+        }
+    }
+
+    #[test]
+    fn push_into_full_buffer() {
+        let mut buf = FrameBuffer::<4>::new();
+        assert_eq!(buf.push(&[1, 2, 3]), 3);
+        assert_eq!(buf.push(&[4, 5, 6]), 1);
+        assert_eq!(buf.len(), 4);
+    }
+
+    #[test]
+    fn consume_frames_and_drop_garbage() {
+        let mut buf = FrameBuffer::<8>::new();
+        buf.push(&[0, 0, 0xFF, 1, 0xFF]);
+        assert_eq!(
+            buf.next_frame(1, locate_0xff).unwrap(),
+            Some(&[0xFF, 1][..])
+        );
+        assert_eq!(buf.dropped(), 2);
+        assert_eq!(buf.len(), 1);
+        assert_eq!(buf.next_frame(1, locate_0xff).unwrap(), None);
+        buf.push(&[2]);
+        assert_eq!(
+            buf.next_frame(1, locate_0xff).unwrap(),
+            Some(&[0xFF, 2][..])
+        );
+        assert_eq!(buf.next_frame(1, locate_0xff).unwrap(), None);
+        assert_eq!(buf.len(), 0);
+    }
+
+    #[test]
+    fn move_bytes_to_the_front_only_if_needed() {
+        let mut buf = FrameBuffer::<4>::new();
+        buf.push(&[0xFF, 1, 0xFF]);
+        assert!(buf.next_frame(1, locate_0xff).unwrap().is_some());
+        assert_eq!((buf.start, buf.end), (2, 3));
+        assert_eq!(buf.push(&[2]), 1);
+        assert_eq!((buf.start, buf.end), (2, 4));
+        assert_eq!(buf.push(&[0xFF, 3]), 2);
+        assert_eq!((buf.start, buf.end), (0, 4));
+        assert_eq!(
+            buf.next_frame(1, locate_0xff).unwrap(),
+            Some(&[0xFF, 2][..])
+        );
+        assert_eq!(
+            buf.next_frame(1, locate_0xff).unwrap(),
+            Some(&[0xFF, 3][..])
+        );
+        assert_eq!(buf.len(), 0);
+    }
+
+    #[test]
+    fn drop_bytes_on_error() {
+        let mut buf = FrameBuffer::<8>::new();
+        buf.push(&[0, 0, 0, 0, 0]);
+        assert!(buf.next_frame(3, locate_0xff).is_err());
+        assert_eq!(buf.dropped(), 3);
+        assert_eq!(buf.len(), 2);
+    }
+
+    #[test]
+    fn drop_bytes_of_full_buffer() {
+        let mut buf = FrameBuffer::<2>::new();
+        buf.push(&[0, 0xFF]);
+        assert_eq!(
+            buf.next_frame(1, locate_0xff).err().unwrap(),
+            Error::BufferSize
+        );
+        assert_eq!(buf.dropped(), 1);
+        buf.push(&[3]);
+        assert_eq!(
+            buf.next_frame(1, locate_0xff).unwrap(),
+            Some(&[0xFF, 3][..])
+        );
+    }
+}
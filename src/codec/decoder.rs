@@ -0,0 +1,135 @@
+//! Incremental frame decoder shared by the stream transports.
+use super::*;
+
+/// A stream transport whose frames can be decoded by a [`FrameDecoder`].
+pub trait Transport: Sized {
+    /// The ADU header of the transport.
+    type Header: AduHeader;
+    /// The state of a new decoder.
+    const INIT: Self;
+    /// The maximum number of bytes of a frame.
+    const MAX_FRAME_LEN: usize;
+    /// Number of bytes that are dropped if no frame could be found.
+    const DROP_ON_ERR: usize;
+    /// Locate the first frame within a buffer.
+    ///
+    /// It returns the start and the size of the frame.
+    fn locate(&mut self, decoder_type: DecoderType, buf: &[u8]) -> Result<Option<(usize, usize)>>;
+    /// Extract the header and the PDU out of a located frame.
+    fn extract<'a>(&'a mut self, frame: &'a [u8]) -> Result<Option<(Self::Header, &'a [u8])>>;
+    /// Encode a request ADU into a frame.
+    fn encode_request(adu: RequestAdu<'_, Self>, buf: &mut [u8]) -> Result<usize>;
+    /// Encode a response ADU into a frame.
+    fn encode_response(adu: ResponseAdu<'_, Self>, buf: &mut [u8]) -> Result<usize>;
+}
+
+pub(crate) type RequestAdu<'a, T> = <<T as Transport>::Header as AduHeader>::RequestAdu<'a>;
+pub(crate) type ResponseAdu<'a, T> = <<T as Transport>::Header as AduHeader>::ResponseAdu<'a>;
+
+/// An incremental decoder with a fixed capacity of `N` bytes.
+///
+/// Received bytes are [pushed](FrameDecoder::push) into the decoder
+/// and complete frames are taken out as ADUs. Consumed bytes and
+/// bytes that do not belong to a frame are discarded automatically.
+///
+/// Frames are borrowed as contiguous slices, so this is not a ring buffer:
+/// the remaining bytes are moved to the front of the buffer once a push
+/// reaches its end.
+///
+/// A complete frame that fails its checksum (CRC or LRC) is consumed
+/// and reported once as a [`RequestError::Frame`] or [`Error`], so that
+/// a server can count the communication errors of the line.
+///
+/// The capacity has to be large enough to hold the largest frame
+/// of the transport, otherwise the decoder cannot be created.
+#[derive(Debug, Clone)]
+pub struct FrameDecoder<T, const N: usize> {
+    buf: FrameBuffer<N>,
+    transport: T,
+    rejected: usize,
+}
+
+impl<T: Transport, const N: usize> Default for FrameDecoder<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Transport, const N: usize> FrameDecoder<T, N> {
+    const CAPACITY_CHECK: () = assert!(
+        N >= T::MAX_FRAME_LEN,
+        "The capacity is too small for the largest frame"
+    );
+
+    /// Create an empty decoder.
+    pub const fn new() -> Self {
+        let () = Self::CAPACITY_CHECK;
+        Self {
+            buf: FrameBuffer::new(),
+            transport: T::INIT,
+            rejected: 0,
+        }
+    }
+    /// Append received bytes.
+    ///
+    /// It returns the number of bytes that fitted into the buffer.
+    pub fn push(&mut self, bytes: &[u8]) -> usize {
+        self.buf.push(bytes)
+    }
+    /// Number of buffered bytes that have not been decoded yet.
+    pub const fn len(&self) -> usize {
+        self.buf.len()
+    }
+    ///  Returns `true` if there are no buffered bytes.
+    pub const fn is_empty(&self) -> bool {
+        self.buf.len() == 0
+    }
+    /// Total number of bytes that have been dropped
+    /// because they did not belong to a valid frame.
+    pub const fn dropped(&self) -> usize {
+        self.buf.dropped() + self.rejected
+    }
+    /// Discard all buffered bytes.
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+    /// Decode the next request.
+    pub fn next_request(
+        &mut self,
+    ) -> core::result::Result<Option<RequestAdu<'_, T>>, RequestError<T::Header>> {
+        let (hdr, pdu) = match self.next_frame(DecoderType::Request)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        let pdu = decode_request_pdu(hdr, pdu)?;
+        Ok(Some(hdr.request_adu(pdu)))
+    }
+    /// Decode the next response.
+    pub fn next_response(&mut self) -> Result<Option<ResponseAdu<'_, T>>> {
+        let (hdr, pdu) = match self.next_frame(DecoderType::Response)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        ResponsePdu::try_from(pdu).map(|pdu| Some(hdr.response_adu(pdu)))
+    }
+
+    #[cfg(feature = "ascii")]
+    pub(crate) fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    fn next_frame(&mut self, decoder_type: DecoderType) -> Result<Option<(T::Header, &[u8])>> {
+        let transport = &mut self.transport;
+        let locate = |buf: &[u8]| transport.locate(decoder_type, buf);
+        let frame = match self.buf.next_frame(T::DROP_ON_ERR, locate)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        let len = frame.len();
+        let res = self.transport.extract(frame);
+        if res.is_err() {
+            self.rejected += len;
+        }
+        res
+    }
+}
@@ -1,9 +1,27 @@
 use crate::{error::*, frame::*};
 use byteorder::{BigEndian, ByteOrder};
-use core::convert::TryFrom;
-
+use core::{convert::TryFrom, fmt};
+
+mod buffer;
+mod decoder;
+mod service;
+mod store;
+#[cfg(feature = "tokio-codec")]
+mod tokio_codec;
+use self::buffer::FrameBuffer;
+use self::decoder::{FrameDecoder, Transport};
+pub use self::{
+    service::{dispatch, Service},
+    store::*,
+};
+
+#[cfg(feature = "ascii")]
+pub mod ascii;
 pub mod rtu;
+pub mod rtu_over_tcp;
 pub mod tcp;
+#[cfg(feature = "udp")]
+pub mod udp;
 
 /// The type of decoding
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -14,26 +32,125 @@ pub enum DecoderType {
 
 type Result<T> = core::result::Result<T, Error>;
 
-impl TryFrom<u8> for Exception {
+/// An error that occurred while decoding a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestError<H> {
+    /// The transport frame could not be decoded.
+    ///
+    /// The sender is unknown, so there is nobody to respond to.
+    Frame(Error),
+    /// The frame is valid, but it contains an invalid PDU.
+    Pdu {
+        /// The header of the frame
+        hdr: H,
+        /// The function code of the PDU
+        fn_code: u8,
+        error: Error,
+    },
+}
+
+impl<H: AduHeader> RequestError<H> {
+    /// The header and the exception that should be sent back.
+    ///
+    /// Frame errors, broadcasts and PDUs without a valid function code
+    /// must not be answered.
+    pub fn exception_response(&self) -> Option<(H, ExceptionResponse)> {
+        match *self {
+            RequestError::Frame(_) => None,
+            RequestError::Pdu { hdr, .. } if hdr.is_broadcast() => None,
+            RequestError::Pdu { fn_code, .. } if fn_code == 0 || fn_code >= 0x80 => None,
+            RequestError::Pdu {
+                hdr,
+                fn_code,
+                error,
+            } => {
+                let exception = error.exception().unwrap_or(Exception::IllegalDataValue);
+                let function = FnCode::from(fn_code);
+                Some((
+                    hdr,
+                    ExceptionResponse {
+                        function,
+                        exception,
+                    },
+                ))
+            }
+        }
+    }
+}
+
+impl<H> From<Error> for RequestError<H> {
+    fn from(err: Error) -> Self {
+        RequestError::Frame(err)
+    }
+}
+
+impl<H> fmt::Display for RequestError<H> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RequestError::Frame(err) => write!(f, "Invalid request frame: {}", err),
+            RequestError::Pdu { fn_code, error, .. } => write!(
+                f,
+                "Invalid request PDU (function code 0x{:0>2X}): {}",
+                fn_code, error
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<H: fmt::Debug> std::error::Error for RequestError<H> {}
+
+/// Decode the request PDU of a valid frame.
+fn decode_request_pdu<H>(
+    hdr: H,
+    pdu: &[u8],
+) -> core::result::Result<RequestPdu<'_>, RequestError<H>> {
+    Request::try_from(pdu).map(RequestPdu).map_err(|error| {
+        error!("Failed to decode request PDU: {}", error);
+        RequestError::Pdu {
+            hdr,
+            fn_code: pdu.first().copied().unwrap_or_default(),
+            error,
+        }
+    })
+}
+
+/// The maximum number of registers a FIFO queue can hold.
+const MAX_FIFO_COUNT: usize = 31;
+
+/// The maximum quantities of a request.
+const MAX_READ_COILS: Quantity = 2000;
+const MAX_WRITE_COILS: Quantity = 1968;
+const MAX_READ_REGISTERS: Quantity = 125;
+const MAX_WRITE_REGISTERS: Quantity = 123;
+const MAX_READ_WRITE_REGISTERS: Quantity = 121;
+
+/// Check the quantity and the address range of a request.
+fn check_quantity(address: Address, quantity: Quantity, max: Quantity) -> Result<()> {
+    if quantity == 0 || quantity > max {
+        return Err(Error::Quantity(quantity));
+    }
+    if u32::from(address) + u32::from(quantity) > 0x1_0000 {
+        return Err(Error::AddressOverflow(address, quantity));
+    }
+    Ok(())
+}
+
+impl TryFrom<u8> for ReadDeviceIdCode {
     type Error = Error;
 
     fn try_from(code: u8) -> Result<Self> {
-        use crate::frame::Exception::*;
-        let ex = match code {
-            0x01 => IllegalFunction,
-            0x02 => IllegalDataAddress,
-            0x03 => IllegalDataValue,
-            0x04 => ServerDeviceFailure,
-            0x05 => Acknowledge,
-            0x06 => ServerDeviceBusy,
-            0x08 => MemoryParityError,
-            0x0A => GatewayPathUnavailable,
-            0x0B => GatewayTargetDevice,
+        use crate::frame::ReadDeviceIdCode::*;
+        let code = match code {
+            0x01 => Basic,
+            0x02 => Regular,
+            0x03 => Extended,
+            0x04 => Specific,
             _ => {
-                return Err(Error::ExceptionCode(code));
+                return Err(Error::ReadDeviceIdCode(code));
             }
         };
-        Ok(ex)
+        Ok(code)
     }
 }
 
@@ -43,7 +160,7 @@ impl From<ExceptionResponse> for [u8; 2] {
         let fn_code: u8 = ex.function.into();
         debug_assert!(fn_code < 0x80);
         data[0] = fn_code + 0x80;
-        data[1] = ex.exception as u8;
+        data[1] = ex.exception.into();
         *data
     }
 }
@@ -52,12 +169,15 @@ impl TryFrom<&[u8]> for ExceptionResponse {
     type Error = Error;
 
     fn try_from(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < 2 {
+            return Err(Error::BufferSize);
+        }
         let fn_err_code = bytes[0];
         if fn_err_code < 0x80 {
             return Err(Error::ExceptionFnCode(fn_err_code));
         }
         let function = (fn_err_code - 0x80).into();
-        let exception = Exception::try_from(bytes[1])?;
+        let exception = Exception::from(bytes[1]);
         Ok(ExceptionResponse {
             function,
             exception,
@@ -91,6 +211,15 @@ impl<'r> TryFrom<&'r [u8]> for Request<'r> {
                 let addr = BigEndian::read_u16(&bytes[1..3]);
                 let quantity = BigEndian::read_u16(&bytes[3..5]);
 
+                match FnCode::from(fn_code) {
+                    f::ReadCoils | f::ReadDiscreteInputs => {
+                        check_quantity(addr, quantity, MAX_READ_COILS)?;
+                    }
+                    f::ReadInputRegisters | f::ReadHoldingRegisters => {
+                        check_quantity(addr, quantity, MAX_READ_REGISTERS)?;
+                    }
+                    _ => {}
+                }
                 match FnCode::from(fn_code) {
                     f::ReadCoils => ReadCoils(addr, quantity),
                     f::ReadDiscreteInputs => ReadDiscreteInputs(addr, quantity),
@@ -106,43 +235,89 @@ impl<'r> TryFrom<&'r [u8]> for Request<'r> {
             ),
             f::WriteMultipleCoils => {
                 let address = BigEndian::read_u16(&bytes[1..3]);
-                let quantity = BigEndian::read_u16(&bytes[3..5]) as usize;
+                let quantity = BigEndian::read_u16(&bytes[3..5]);
+                check_quantity(address, quantity, MAX_WRITE_COILS)?;
+                let quantity = quantity as usize;
                 let byte_count = bytes[5];
-                if bytes.len() < (6 + byte_count as usize) {
+                if byte_count as usize != packed_coils_len(quantity)
+                    || bytes.len() < (6 + byte_count as usize)
+                {
                     return Err(Error::ByteCount(byte_count));
                 }
-                let data = &bytes[6..];
-                let coils = Coils { quantity, data };
+                let coils = Coils::new(&bytes[6..6 + byte_count as usize], quantity)?;
                 WriteMultipleCoils(address, coils)
             }
             f::WriteMultipleRegisters => {
                 let address = BigEndian::read_u16(&bytes[1..3]);
-                let quantity = BigEndian::read_u16(&bytes[3..5]) as usize;
+                let quantity = BigEndian::read_u16(&bytes[3..5]);
+                check_quantity(address, quantity, MAX_WRITE_REGISTERS)?;
+                let quantity = quantity as usize;
                 let byte_count = bytes[5];
-                if bytes.len() < (6 + byte_count as usize) {
+                if byte_count as usize != quantity * 2 || bytes.len() < (6 + byte_count as usize) {
                     return Err(Error::ByteCount(byte_count));
                 }
-                let data = Data {
-                    quantity,
-                    data: &bytes[6..6 + byte_count as usize],
-                };
+                let data = Data::new(&bytes[6..6 + byte_count as usize], quantity)?;
                 WriteMultipleRegisters(address, data)
             }
             f::ReadWriteMultipleRegisters => {
                 let read_address = BigEndian::read_u16(&bytes[1..3]);
                 let read_quantity = BigEndian::read_u16(&bytes[3..5]);
                 let write_address = BigEndian::read_u16(&bytes[5..7]);
-                let write_quantity = BigEndian::read_u16(&bytes[7..9]) as usize;
+                let write_quantity = BigEndian::read_u16(&bytes[7..9]);
+                check_quantity(read_address, read_quantity, MAX_READ_REGISTERS)?;
+                check_quantity(write_address, write_quantity, MAX_READ_WRITE_REGISTERS)?;
+                let write_quantity = write_quantity as usize;
                 let write_count = bytes[9];
-                if bytes.len() < (10 + write_count as usize) {
+                if write_count as usize != write_quantity * 2
+                    || bytes.len() < (10 + write_count as usize)
+                {
                     return Err(Error::ByteCount(write_count));
                 }
-                let data = Data {
-                    quantity: write_quantity as usize,
-                    data: &bytes[10..10 + write_count as usize],
-                };
+                let data = Data::new(&bytes[10..10 + write_count as usize], write_quantity)?;
                 ReadWriteMultipleRegisters(read_address, read_quantity, write_address, data)
             }
+            f::MaskWriteRegister => MaskWriteRegister(
+                BigEndian::read_u16(&bytes[1..3]),
+                BigEndian::read_u16(&bytes[3..5]),
+                BigEndian::read_u16(&bytes[5..7]),
+            ),
+            f::ReadFifoQueue => ReadFifoQueue(BigEndian::read_u16(&bytes[1..3])),
+            f::ReadFileRecord | f::WriteFileRecord => {
+                let byte_count = bytes[1];
+                if bytes.len() < (2 + byte_count as usize) {
+                    return Err(Error::ByteCount(byte_count));
+                }
+                let data = &bytes[2..2 + byte_count as usize];
+                match FnCode::from(fn_code) {
+                    f::ReadFileRecord => ReadFileRecord(decode_file_sub_requests(data)?),
+                    f::WriteFileRecord => WriteFileRecord(decode_file_records(data)?),
+                    _ => unreachable!(),
+                }
+            }
+            f::EncapsulatedInterfaceTransport => match MeiType::from(bytes[1]) {
+                MeiType::ReadDeviceIdentification => {
+                    if bytes.len() < 4 {
+                        return Err(Error::BufferSize);
+                    }
+                    ReadDeviceIdentification(ReadDeviceIdCode::try_from(bytes[2])?, bytes[3])
+                }
+                _ => Custom(FnCode::EncapsulatedInterfaceTransport, &bytes[1..]),
+            },
+            #[cfg(feature = "rtu")]
+            f::ReadExceptionStatus => ReadExceptionStatus,
+            #[cfg(feature = "rtu")]
+            f::Diagnostics => {
+                let sub_fn = DiagnosticSubFunction::from(BigEndian::read_u16(&bytes[1..3]));
+                let data = decode_diagnostics_data(&bytes[3..])?;
+                sub_fn.validate_request(&data)?;
+                Diagnostics(sub_fn, data)
+            }
+            #[cfg(feature = "rtu")]
+            f::GetCommEventCounter => GetCommEventCounter,
+            #[cfg(feature = "rtu")]
+            f::GetCommEventLog => GetCommEventLog,
+            #[cfg(feature = "rtu")]
+            f::ReportServerId => ReportServerId,
             _ => match fn_code {
                 fn_code if fn_code < 0x80 => Custom(FnCode::Custom(fn_code), &bytes[1..]),
                 _ => return Err(Error::FnCode(fn_code)),
@@ -157,6 +332,9 @@ impl<'r> TryFrom<&'r [u8]> for Response<'r> {
 
     fn try_from(bytes: &'r [u8]) -> Result<Self> {
         use crate::frame::Response::*;
+        if bytes.is_empty() {
+            return Err(Error::BufferSize);
+        }
         let fn_code = bytes[0];
         if bytes.len() < min_response_pdu_len(fn_code.into()) {
             return Err(Error::BufferSize);
@@ -174,8 +352,8 @@ impl<'r> TryFrom<&'r [u8]> for Response<'r> {
                 let quantity = byte_count * 8;
 
                 match FnCode::from(fn_code) {
-                    FnCode::ReadCoils => ReadCoils(Coils { quantity, data }),
-                    FnCode::ReadDiscreteInputs => ReadDiscreteInputs(Coils { quantity, data }),
+                    FnCode::ReadCoils => ReadCoils(Coils::new(data, quantity)?),
+                    FnCode::ReadDiscreteInputs => ReadDiscreteInputs(Coils::new(data, quantity)?),
                     _ => unreachable!(),
                 }
             }
@@ -197,8 +375,10 @@ impl<'r> TryFrom<&'r [u8]> for Response<'r> {
                 if byte_count + 2 > bytes.len() {
                     return Err(Error::BufferSize);
                 }
-                let data = &bytes[2..2 + byte_count];
-                let data = Data { quantity, data };
+                if !byte_count.is_multiple_of(2) {
+                    return Err(Error::ByteCount(bytes[1]));
+                }
+                let data = Data::new(&bytes[2..2 + byte_count], quantity)?;
 
                 match FnCode::from(fn_code) {
                     f::ReadInputRegisters => ReadInputRegisters(data),
@@ -207,12 +387,304 @@ impl<'r> TryFrom<&'r [u8]> for Response<'r> {
                     _ => unreachable!(),
                 }
             }
-            _ => Custom(FnCode::from(fn_code), &bytes[1..]),
+            f::MaskWriteRegister => MaskWriteRegister(
+                BigEndian::read_u16(&bytes[1..3]),
+                BigEndian::read_u16(&bytes[3..5]),
+                BigEndian::read_u16(&bytes[5..7]),
+            ),
+            f::ReadFifoQueue => {
+                let byte_count = BigEndian::read_u16(&bytes[1..3]) as usize;
+                let fifo_count = BigEndian::read_u16(&bytes[3..5]);
+                let quantity = fifo_count as usize;
+                if quantity > MAX_FIFO_COUNT {
+                    return Err(Error::FifoCount(fifo_count));
+                }
+                if byte_count != 2 + quantity * 2 {
+                    return Err(Error::LengthMismatch(byte_count, 2 + quantity * 2));
+                }
+                if byte_count + 3 > bytes.len() {
+                    return Err(Error::BufferSize);
+                }
+                let data = &bytes[5..5 + quantity * 2];
+                ReadFifoQueue(Data::new(data, quantity)?)
+            }
+            f::ReadFileRecord | f::WriteFileRecord => {
+                let byte_count = bytes[1];
+                if byte_count as usize + 2 > bytes.len() {
+                    return Err(Error::BufferSize);
+                }
+                let data = &bytes[2..2 + byte_count as usize];
+                match FnCode::from(fn_code) {
+                    f::ReadFileRecord => ReadFileRecord(decode_file_sub_responses(data)?),
+                    f::WriteFileRecord => WriteFileRecord(decode_file_records(data)?),
+                    _ => unreachable!(),
+                }
+            }
+            f::EncapsulatedInterfaceTransport => match MeiType::from(bytes[1]) {
+                MeiType::ReadDeviceIdentification => {
+                    let pdu_len = match mei_response_pdu_len(bytes)? {
+                        Some(pdu_len) if pdu_len <= bytes.len() => pdu_len,
+                        _ => return Err(Error::BufferSize),
+                    };
+                    ReadDeviceIdentification(DeviceIdentification {
+                        read_device_id_code: ReadDeviceIdCode::try_from(bytes[2])?,
+                        conformity_level: bytes[3],
+                        more_follows: bytes[4] == 0xFF,
+                        next_object_id: bytes[5],
+                        number_of_objects: bytes[6],
+                        objects: &bytes[7..pdu_len],
+                    })
+                }
+                _ => Custom(FnCode::EncapsulatedInterfaceTransport, &bytes[1..]),
+            },
+            #[cfg(feature = "rtu")]
+            f::ReadExceptionStatus => ReadExceptionStatus(bytes[1]),
+            #[cfg(feature = "rtu")]
+            f::Diagnostics => Diagnostics(
+                BigEndian::read_u16(&bytes[1..3]).into(),
+                decode_diagnostics_data(&bytes[3..])?,
+            ),
+            #[cfg(feature = "rtu")]
+            f::GetCommEventCounter => GetCommEventCounter(
+                BigEndian::read_u16(&bytes[1..3]),
+                BigEndian::read_u16(&bytes[3..5]),
+            ),
+            #[cfg(feature = "rtu")]
+            f::GetCommEventLog => {
+                let byte_count = bytes[1];
+                if byte_count < 6 {
+                    return Err(Error::ByteCount(byte_count));
+                }
+                if byte_count as usize + 2 > bytes.len() {
+                    return Err(Error::BufferSize);
+                }
+                GetCommEventLog(
+                    BigEndian::read_u16(&bytes[2..4]),
+                    BigEndian::read_u16(&bytes[4..6]),
+                    BigEndian::read_u16(&bytes[6..8]),
+                    &bytes[8..2 + byte_count as usize],
+                )
+            }
+            #[cfg(feature = "rtu")]
+            f::ReportServerId => {
+                let byte_count = bytes[1];
+                if byte_count == 0 {
+                    return Err(Error::ByteCount(byte_count));
+                }
+                if byte_count as usize + 2 > bytes.len() {
+                    return Err(Error::BufferSize);
+                }
+                let run_indicator = bytes[1 + byte_count as usize];
+                ReportServerId(&bytes[2..1 + byte_count as usize], run_indicator != 0x00)
+            }
+            _ => match fn_code {
+                fn_code if fn_code < 0x80 => Custom(FnCode::from(fn_code), &bytes[1..]),
+                _ => return Err(Error::FnCode(fn_code)),
+            },
+        };
+        Ok(rsp)
+    }
+}
+
+fn decode_file_sub_requests(data: &[u8]) -> Result<FileSubRequests<'_>> {
+    if data.is_empty() || !data.len().is_multiple_of(7) {
+        return Err(Error::ByteCount(data.len() as u8));
+    }
+    for sub_request in data.chunks(7) {
+        check_reference_type(sub_request[0])?;
+    }
+    Ok(FileSubRequests { data })
+}
+
+fn decode_file_sub_responses(data: &[u8]) -> Result<FileSubResponses<'_>> {
+    if data.is_empty() {
+        return Err(Error::ByteCount(0));
+    }
+    let mut rest = data;
+    while !rest.is_empty() {
+        let len = rest[0] as usize;
+        if len.is_multiple_of(2) || len + 1 > rest.len() {
+            return Err(Error::ByteCount(data.len() as u8));
+        }
+        check_reference_type(rest[1])?;
+        rest = &rest[len + 1..];
+    }
+    Ok(FileSubResponses { data })
+}
+
+fn decode_file_records(data: &[u8]) -> Result<FileRecords<'_>> {
+    if data.is_empty() {
+        return Err(Error::ByteCount(0));
+    }
+    let mut rest = data;
+    while !rest.is_empty() {
+        if rest.len() < 7 {
+            return Err(Error::ByteCount(data.len() as u8));
+        }
+        check_reference_type(rest[0])?;
+        let len = 7 + BigEndian::read_u16(&rest[5..7]) as usize * 2;
+        if len > rest.len() {
+            return Err(Error::ByteCount(data.len() as u8));
+        }
+        rest = &rest[len..];
+    }
+    Ok(FileRecords { data })
+}
+
+#[cfg(feature = "rtu")]
+fn decode_diagnostics_data(data: &[u8]) -> Result<Data<'_>> {
+    if !data.len().is_multiple_of(2) {
+        return Err(Error::ByteCount(data.len() as u8));
+    }
+    Data::new(data, data.len() / 2)
+}
+
+fn check_reference_type(ref_type: u8) -> Result<()> {
+    if ref_type != FILE_REFERENCE_TYPE {
+        return Err(Error::ReferenceType(ref_type));
+    }
+    Ok(())
+}
+
+impl<'r> Response<'r> {
+    /// Decode a response PDU with the knowledge of the originating request.
+    ///
+    /// In contrast to `Response::try_from` the exact quantity of
+    /// requested coils is known and the echoed fields are validated
+    /// against the request.
+    pub fn decode_for(req: &Request, bytes: &'r [u8]) -> Result<Self> {
+        use crate::frame::Response::*;
+
+        let rsp = Response::try_from(bytes)?;
+        if FnCode::from(*req) != FnCode::from(rsp) {
+            return Err(Error::FnCode(bytes[0]));
+        }
+        let rsp = match (*req, rsp) {
+            (Request::ReadCoils(_, quantity), ReadCoils(coils))
+            | (Request::ReadDiscreteInputs(_, quantity), ReadDiscreteInputs(coils)) => {
+                let quantity = quantity as usize;
+                let coils =
+                    Coils::new(coils.data, quantity).map_err(|_| Error::ByteCount(bytes[1]))?;
+                match rsp {
+                    ReadCoils(_) => ReadCoils(coils),
+                    ReadDiscreteInputs(_) => ReadDiscreteInputs(coils),
+                    _ => unreachable!(),
+                }
+            }
+            (Request::ReadInputRegisters(_, quantity), ReadInputRegisters(words))
+            | (Request::ReadHoldingRegisters(_, quantity), ReadHoldingRegisters(words))
+            | (
+                Request::ReadWriteMultipleRegisters(_, quantity, _, _),
+                ReadWriteMultipleRegisters(words),
+            ) => {
+                if words.data.len() != quantity as usize * 2 {
+                    return Err(Error::ByteCount(bytes[1]));
+                }
+                rsp
+            }
+            (Request::WriteSingleCoil(address, _), WriteSingleCoil(rsp_address)) => {
+                check_echo(address, rsp_address, Error::AddressMismatch)?;
+                rsp
+            }
+            (Request::WriteMultipleCoils(address, coils), WriteMultipleCoils(rsp_address, cnt)) => {
+                check_echo(address, rsp_address, Error::AddressMismatch)?;
+                check_echo(coils.len() as u16, cnt, Error::QuantityMismatch)?;
+                rsp
+            }
+            (
+                Request::WriteMultipleRegisters(address, words),
+                WriteMultipleRegisters(rsp_address, cnt),
+            ) => {
+                check_echo(address, rsp_address, Error::AddressMismatch)?;
+                check_echo(words.len() as u16, cnt, Error::QuantityMismatch)?;
+                rsp
+            }
+            (
+                Request::WriteSingleRegister(address, word),
+                WriteSingleRegister(rsp_address, value),
+            ) => {
+                check_echo(address, rsp_address, Error::AddressMismatch)?;
+                check_echo(word, value, Error::ValueMismatch)?;
+                rsp
+            }
+            (
+                Request::MaskWriteRegister(address, and_mask, or_mask),
+                MaskWriteRegister(rsp_address, rsp_and_mask, rsp_or_mask),
+            ) => {
+                check_echo(address, rsp_address, Error::AddressMismatch)?;
+                check_echo(and_mask, rsp_and_mask, Error::ValueMismatch)?;
+                check_echo(or_mask, rsp_or_mask, Error::ValueMismatch)?;
+                rsp
+            }
+            #[cfg(feature = "rtu")]
+            (Request::Diagnostics(sub_fn, _), Diagnostics(rsp_sub_fn, _)) => {
+                check_echo(sub_fn.into(), rsp_sub_fn.into(), Error::ValueMismatch)?;
+                rsp
+            }
+            _ => rsp,
         };
         Ok(rsp)
     }
 }
 
+fn check_echo(expected: u16, actual: u16, err: fn(u16, u16) -> Error) -> Result<()> {
+    if expected != actual {
+        return Err(err(expected, actual));
+    }
+    Ok(())
+}
+
+/// Extract the PDU length out of an encapsulated interface transport request PDU.
+pub(crate) fn mei_request_pdu_len(pdu_buf: &[u8]) -> Result<Option<usize>> {
+    if pdu_buf.len() < 2 {
+        return Ok(None);
+    }
+    match MeiType::from(pdu_buf[1]) {
+        MeiType::ReadDeviceIdentification => Ok(Some(4)),
+        _ => Err(Error::MeiType(pdu_buf[1])),
+    }
+}
+
+/// Extract the PDU length out of an encapsulated interface transport response PDU.
+pub(crate) fn mei_response_pdu_len(pdu_buf: &[u8]) -> Result<Option<usize>> {
+    if pdu_buf.len() < 2 {
+        return Ok(None);
+    }
+    match MeiType::from(pdu_buf[1]) {
+        MeiType::ReadDeviceIdentification => {
+            if pdu_buf.len() < 7 {
+                // incomplete frame
+                return Ok(None);
+            }
+            let mut len = 7;
+            for _ in 0..pdu_buf[6] {
+                if pdu_buf.len() < len + 2 {
+                    // incomplete frame
+                    return Ok(None);
+                }
+                len += 2 + pdu_buf[len + 1] as usize;
+            }
+            Ok(Some(len))
+        }
+        _ => Err(Error::MeiType(pdu_buf[1])),
+    }
+}
+
+impl<'r> TryFrom<&'r [u8]> for ResponsePdu<'r> {
+    type Error = Error;
+
+    /// Decode a response PDU that might be an exception response.
+    fn try_from(bytes: &'r [u8]) -> Result<Self> {
+        if bytes.is_empty() {
+            return Err(Error::BufferSize);
+        }
+        if bytes[0] >= 0x80 {
+            return ExceptionResponse::try_from(bytes).map(|ex| ResponsePdu(Err(ex)));
+        }
+        Response::try_from(bytes).map(|rsp| ResponsePdu(Ok(rsp)))
+    }
+}
+
 /// Encode a struct into a buffer.
 pub trait Encode {
     fn encode(&self, buf: &mut [u8]) -> Result<usize>;
@@ -265,12 +737,35 @@ impl<'r> Encode for Request<'r> {
                     buf[idx + 10] = *byte;
                 }
             }
+            MaskWriteRegister(address, and_mask, or_mask) => {
+                BigEndian::write_u16(&mut buf[1..], *address);
+                BigEndian::write_u16(&mut buf[3..], *and_mask);
+                BigEndian::write_u16(&mut buf[5..], *or_mask);
+            }
+            ReadFifoQueue(address) => {
+                BigEndian::write_u16(&mut buf[1..], *address);
+            }
+            ReadFileRecord(FileSubRequests { data }) | WriteFileRecord(FileRecords { data }) => {
+                buf[1] = data.len() as u8;
+                buf[2..2 + data.len()].copy_from_slice(data);
+            }
+            ReadDeviceIdentification(code, object_id) => {
+                buf[1] = MeiType::ReadDeviceIdentification.into();
+                buf[2] = *code as u8;
+                buf[3] = *object_id;
+            }
+            #[cfg(feature = "rtu")]
+            ReadExceptionStatus | GetCommEventCounter | GetCommEventLog | ReportServerId => {}
+            #[cfg(feature = "rtu")]
+            Diagnostics(sub_fn, words) => {
+                BigEndian::write_u16(&mut buf[1..], (*sub_fn).into());
+                words.copy_to(&mut buf[3..]);
+            }
             Custom(_, custom_data) => {
                 custom_data.iter().enumerate().for_each(|(idx, d)| {
                     buf[idx + 1] = *d;
                 });
             }
-            _ => panic!(),
         }
         Ok(self.pdu_len())
     }
@@ -303,20 +798,77 @@ impl<'r> Encode for Response<'r> {
                 BigEndian::write_u16(&mut buf[1..], *address);
                 BigEndian::write_u16(&mut buf[3..], *payload);
             }
+            MaskWriteRegister(address, and_mask, or_mask) => {
+                BigEndian::write_u16(&mut buf[1..], *address);
+                BigEndian::write_u16(&mut buf[3..], *and_mask);
+                BigEndian::write_u16(&mut buf[5..], *or_mask);
+            }
+            ReadFifoQueue(registers) => {
+                let fifo_count = registers.len();
+                if fifo_count > MAX_FIFO_COUNT {
+                    return Err(Error::FifoCount(fifo_count as u16));
+                }
+                BigEndian::write_u16(&mut buf[1..], (2 + fifo_count * 2) as u16);
+                BigEndian::write_u16(&mut buf[3..], fifo_count as u16);
+                registers.copy_to(&mut buf[5..]);
+            }
+            ReadFileRecord(FileSubResponses { data }) | WriteFileRecord(FileRecords { data }) => {
+                buf[1] = data.len() as u8;
+                buf[2..2 + data.len()].copy_from_slice(data);
+            }
+            ReadDeviceIdentification(dev_id) => {
+                buf[1] = MeiType::ReadDeviceIdentification.into();
+                buf[2] = dev_id.read_device_id_code as u8;
+                buf[3] = dev_id.conformity_level;
+                buf[4] = if dev_id.more_follows { 0xFF } else { 0x00 };
+                buf[5] = dev_id.next_object_id;
+                buf[6] = dev_id.number_of_objects;
+                buf[7..7 + dev_id.objects.len()].copy_from_slice(dev_id.objects);
+            }
+            #[cfg(feature = "rtu")]
+            ReadExceptionStatus(status) => {
+                buf[1] = *status;
+            }
+            #[cfg(feature = "rtu")]
+            Diagnostics(sub_fn, words) => {
+                BigEndian::write_u16(&mut buf[1..], (*sub_fn).into());
+                words.copy_to(&mut buf[3..]);
+            }
+            #[cfg(feature = "rtu")]
+            GetCommEventCounter(status, event_count) => {
+                BigEndian::write_u16(&mut buf[1..], *status);
+                BigEndian::write_u16(&mut buf[3..], *event_count);
+            }
+            #[cfg(feature = "rtu")]
+            GetCommEventLog(status, event_count, message_count, events) => {
+                buf[1] = (6 + events.len()) as u8;
+                BigEndian::write_u16(&mut buf[2..], *status);
+                BigEndian::write_u16(&mut buf[4..], *event_count);
+                BigEndian::write_u16(&mut buf[6..], *message_count);
+                buf[8..8 + events.len()].copy_from_slice(events);
+            }
+            #[cfg(feature = "rtu")]
+            ReportServerId(server_id, run_indicator) => {
+                buf[1] = (server_id.len() + 1) as u8;
+                buf[2..2 + server_id.len()].copy_from_slice(server_id);
+                buf[2 + server_id.len()] = if *run_indicator { 0xFF } else { 0x00 };
+            }
             Custom(_, custom_data) => {
                 for (idx, d) in custom_data.iter().enumerate() {
                     buf[idx + 1] = *d;
                 }
             }
-            _ => {
-                // TODO:
-                unimplemented!()
-            }
         }
         Ok(self.pdu_len())
     }
 }
 
+impl<'r> Encode for RequestPdu<'r> {
+    fn encode(&self, buf: &mut [u8]) -> Result<usize> {
+        self.0.encode(buf)
+    }
+}
+
 impl<'r> Encode for ResponsePdu<'r> {
     fn encode(&self, buf: &mut [u8]) -> Result<usize> {
         match self.0 {
@@ -343,6 +895,12 @@ fn min_request_pdu_len(fn_code: FnCode) -> usize {
         WriteMultipleCoils => 6,
         WriteMultipleRegisters => 6,
         ReadWriteMultipleRegisters => 10,
+        MaskWriteRegister => 7,
+        ReadFifoQueue => 3,
+        ReadFileRecord | WriteFileRecord => 2,
+        EncapsulatedInterfaceTransport => 2,
+        #[cfg(feature = "rtu")]
+        Diagnostics => 3,
         _ => 1,
     }
 }
@@ -357,6 +915,20 @@ fn min_response_pdu_len(fn_code: FnCode) -> usize {
         | ReadWriteMultipleRegisters => 2,
         WriteSingleCoil => 3,
         WriteMultipleCoils | WriteSingleRegister | WriteMultipleRegisters => 5,
+        MaskWriteRegister => 7,
+        ReadFifoQueue => 5,
+        ReadFileRecord | WriteFileRecord => 2,
+        EncapsulatedInterfaceTransport => 2,
+        #[cfg(feature = "rtu")]
+        ReadExceptionStatus => 2,
+        #[cfg(feature = "rtu")]
+        Diagnostics => 3,
+        #[cfg(feature = "rtu")]
+        GetCommEventCounter => 5,
+        #[cfg(feature = "rtu")]
+        GetCommEventLog => 8,
+        #[cfg(feature = "rtu")]
+        ReportServerId => 3,
         _ => 1,
     }
 }
@@ -390,6 +962,91 @@ mod tests {
                 exception: Exception::IllegalDataAddress,
             }
         );
+
+        let bytes: &[u8] = &[0x83, 0x42];
+        let rsp = ExceptionResponse::try_from(bytes).unwrap();
+        assert_eq!(rsp.exception, Exception::Other(0x42));
+        assert_eq!(<[u8; 2]>::from(rsp), [0x83, 0x42]);
+
+        let bytes: &[u8] = &[0x83];
+        assert_eq!(ExceptionResponse::try_from(bytes), Err(Error::BufferSize));
+    }
+
+    #[test]
+    fn exception_codes() {
+        for code in 0..=u8::MAX {
+            assert_eq!(u8::from(Exception::from(code)), code);
+        }
+        assert_eq!(Exception::from(0x02), Exception::IllegalDataAddress);
+        assert_eq!(Exception::from(0x07), Exception::Other(0x07));
+    }
+
+    #[test]
+    fn response_pdu_from_bytes() {
+        let bytes: &[u8] = &[0x83, 0x02];
+        assert_eq!(
+            ResponsePdu::try_from(bytes),
+            Ok(ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::ReadHoldingRegisters,
+                exception: Exception::IllegalDataAddress,
+            })))
+        );
+        let bytes: &[u8] = &[0xC1, 0x0C];
+        assert_eq!(
+            ResponsePdu::try_from(bytes),
+            Ok(ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::Custom(0x41),
+                exception: Exception::Other(0x0C),
+            })))
+        );
+        let bytes: &[u8] = &[0x06, 0x00, 0x01, 0xAB, 0xCD];
+        assert_eq!(
+            ResponsePdu::try_from(bytes),
+            Ok(ResponsePdu(Ok(Response::WriteSingleRegister(0x01, 0xABCD))))
+        );
+        let bytes: &[u8] = &[0x83];
+        assert!(ResponsePdu::try_from(bytes).is_err());
+        let bytes: &[u8] = &[];
+        assert!(ResponsePdu::try_from(bytes).is_err());
+    }
+
+    #[test]
+    fn exception_frame_is_not_a_response() {
+        let bytes: &[u8] = &[0x83, 0x02];
+        assert_eq!(Response::try_from(bytes), Err(Error::FnCode(0x83)));
+    }
+
+    #[test]
+    fn read_device_id_code_from_u8() {
+        assert_eq!(
+            ReadDeviceIdCode::try_from(0x01).unwrap(),
+            ReadDeviceIdCode::Basic
+        );
+        assert_eq!(
+            ReadDeviceIdCode::try_from(0x04).unwrap(),
+            ReadDeviceIdCode::Specific
+        );
+        assert_eq!(
+            ReadDeviceIdCode::try_from(0x05).err().unwrap(),
+            Error::ReadDeviceIdCode(0x05)
+        );
+    }
+
+    #[test]
+    fn test_mei_response_pdu_len() {
+        assert_eq!(mei_response_pdu_len(&[0x2B]).unwrap(), None);
+        assert_eq!(
+            mei_response_pdu_len(&[0x2B, 0x0D]).err().unwrap(),
+            Error::MeiType(0x0D)
+        );
+        let buf = &[
+            0x2B, 0x0E, 0x01, 0x01, 0x00, 0x00, 0x02, 0x00, 0x01, b'a', 0x01,
+        ];
+        assert_eq!(mei_response_pdu_len(buf).unwrap(), None);
+        let buf = &[
+            0x2B, 0x0E, 0x01, 0x01, 0x00, 0x00, 0x02, 0x00, 0x01, b'a', 0x01, 0x05,
+        ];
+        assert_eq!(mei_response_pdu_len(buf).unwrap(), Some(17));
     }
 
     #[test]
@@ -405,6 +1062,19 @@ mod tests {
         assert_eq!(min_request_pdu_len(WriteMultipleCoils), 6);
         assert_eq!(min_request_pdu_len(WriteMultipleRegisters), 6);
         assert_eq!(min_request_pdu_len(ReadWriteMultipleRegisters), 10);
+        assert_eq!(min_request_pdu_len(MaskWriteRegister), 7);
+        assert_eq!(min_request_pdu_len(ReadFifoQueue), 3);
+        assert_eq!(min_request_pdu_len(ReadFileRecord), 2);
+        assert_eq!(min_request_pdu_len(WriteFileRecord), 2);
+        assert_eq!(min_request_pdu_len(EncapsulatedInterfaceTransport), 2);
+        #[cfg(feature = "rtu")]
+        {
+            assert_eq!(min_request_pdu_len(ReadExceptionStatus), 1);
+            assert_eq!(min_request_pdu_len(Diagnostics), 3);
+            assert_eq!(min_request_pdu_len(GetCommEventCounter), 1);
+            assert_eq!(min_request_pdu_len(GetCommEventLog), 1);
+            assert_eq!(min_request_pdu_len(ReportServerId), 1);
+        }
     }
 
     #[test]
@@ -420,6 +1090,19 @@ mod tests {
         assert_eq!(min_response_pdu_len(WriteMultipleCoils), 5);
         assert_eq!(min_response_pdu_len(WriteMultipleRegisters), 5);
         assert_eq!(min_response_pdu_len(ReadWriteMultipleRegisters), 2);
+        assert_eq!(min_response_pdu_len(MaskWriteRegister), 7);
+        assert_eq!(min_response_pdu_len(ReadFifoQueue), 5);
+        assert_eq!(min_response_pdu_len(ReadFileRecord), 2);
+        assert_eq!(min_response_pdu_len(WriteFileRecord), 2);
+        assert_eq!(min_response_pdu_len(EncapsulatedInterfaceTransport), 2);
+        #[cfg(feature = "rtu")]
+        {
+            assert_eq!(min_response_pdu_len(ReadExceptionStatus), 2);
+            assert_eq!(min_response_pdu_len(Diagnostics), 3);
+            assert_eq!(min_response_pdu_len(GetCommEventCounter), 5);
+            assert_eq!(min_response_pdu_len(GetCommEventLog), 8);
+            assert_eq!(min_response_pdu_len(ReportServerId), 3);
+        }
     }
 
     mod serialize_requests {
@@ -587,6 +1270,94 @@ mod tests {
             assert_eq!(bytes[13], 0x12);
         }
 
+        #[test]
+        fn mask_write_register() {
+            let bytes = &mut [0; 7];
+            Request::MaskWriteRegister(0x04, 0x00F2, 0x0025)
+                .encode(bytes)
+                .unwrap();
+            assert_eq!(bytes[0], 0x16);
+            assert_eq!(bytes[1], 0x00);
+            assert_eq!(bytes[2], 0x04);
+            assert_eq!(bytes[3], 0x00);
+            assert_eq!(bytes[4], 0xF2);
+            assert_eq!(bytes[5], 0x00);
+            assert_eq!(bytes[6], 0x25);
+        }
+
+        #[test]
+        fn read_fifo_queue() {
+            let bytes = &mut [0; 3];
+            Request::ReadFifoQueue(0x04DE).encode(bytes).unwrap();
+            assert_eq!(bytes[0], 0x18);
+            assert_eq!(bytes[1], 0x04);
+            assert_eq!(bytes[2], 0xDE);
+        }
+
+        #[test]
+        fn read_file_record() {
+            let buf = &mut [0; 14];
+            let sub_requests = FileSubRequests::from_sub_requests(
+                &[
+                    FileSubRequest {
+                        file: 4,
+                        record: 1,
+                        length: 2,
+                    },
+                    FileSubRequest {
+                        file: 3,
+                        record: 9,
+                        length: 2,
+                    },
+                ],
+                buf,
+            )
+            .unwrap();
+            let bytes = &mut [0; 16];
+            Request::ReadFileRecord(sub_requests).encode(bytes).unwrap();
+            assert_eq!(
+                bytes,
+                &[
+                    0x14, 0x0E, 0x06, 0x00, 0x04, 0x00, 0x01, 0x00, 0x02, 0x06, 0x00, 0x03, 0x00,
+                    0x09, 0x00, 0x02
+                ]
+            );
+        }
+
+        #[test]
+        fn write_file_record() {
+            let buf = &mut [0; 6];
+            let data = Data::from_words(&[0x06AF, 0x04BE, 0x100D], buf).unwrap();
+            let buf = &mut [0; 13];
+            let records = FileRecords::from_records(
+                &[FileRecord {
+                    file: 4,
+                    record: 7,
+                    data,
+                }],
+                buf,
+            )
+            .unwrap();
+            let bytes = &mut [0; 15];
+            Request::WriteFileRecord(records).encode(bytes).unwrap();
+            assert_eq!(
+                bytes,
+                &[
+                    0x15, 0x0D, 0x06, 0x00, 0x04, 0x00, 0x07, 0x00, 0x03, 0x06, 0xAF, 0x04, 0xBE,
+                    0x10, 0x0D
+                ]
+            );
+        }
+
+        #[test]
+        fn read_device_identification() {
+            let bytes = &mut [0; 4];
+            Request::ReadDeviceIdentification(ReadDeviceIdCode::Basic, 0x00)
+                .encode(bytes)
+                .unwrap();
+            assert_eq!(bytes, &[0x2B, 0x0E, 0x01, 0x00]);
+        }
+
         #[test]
         fn custom() {
             let bytes = &mut [0; 5];
@@ -623,16 +1394,86 @@ mod tests {
         }
 
         #[test]
-        fn read_discrete_inputs() {
-            let data: &[u8] = &[2, 0x00, 0x03, 0x00, 19];
-            let req = Request::try_from(data).unwrap();
-            assert_eq!(req, Request::ReadDiscreteInputs(0x03, 19));
+        fn read_with_invalid_quantity() {
+            let data: &[u8] = &[0x01, 0x00, 0x00, 0x00, 0x00];
+            assert_eq!(Request::try_from(data), Err(Error::Quantity(0)));
+            let data: &[u8] = &[0x02, 0x00, 0x00, 0x07, 0xD1];
+            assert_eq!(Request::try_from(data), Err(Error::Quantity(2001)));
+            let data: &[u8] = &[0x02, 0x00, 0x00, 0x07, 0xD0];
+            assert!(Request::try_from(data).is_ok());
+            let data: &[u8] = &[0x03, 0x00, 0x00, 0x00, 0x7E];
+            assert_eq!(Request::try_from(data), Err(Error::Quantity(126)));
+            let data: &[u8] = &[0x04, 0x00, 0x00, 0x00, 0x7D];
+            assert!(Request::try_from(data).is_ok());
+            assert_eq!(
+                Error::Quantity(0).exception(),
+                Some(Exception::IllegalDataValue)
+            );
         }
 
         #[test]
-        fn write_single_coil() {
-            let bytes: &[u8] = &[5, 0x12, 0x34, 0xFF, 0x00];
-            let req = Request::try_from(bytes).unwrap();
+        fn write_with_inconsistent_byte_count() {
+            let data: &[u8] = &[0x0F, 0x00, 0x00, 0x00, 0x09, 0x01, 0xFF, 0x01];
+            assert_eq!(Request::try_from(data), Err(Error::ByteCount(1)));
+            let data: &[u8] = &[0x0F, 0x00, 0x00, 0x00, 0x08, 0x02, 0xFF, 0x01];
+            assert_eq!(Request::try_from(data), Err(Error::ByteCount(2)));
+            let data: &[u8] = &[0x10, 0x00, 0x00, 0x00, 0x02, 0x02, 0xAB, 0xCD];
+            assert_eq!(Request::try_from(data), Err(Error::ByteCount(2)));
+            let data: &[u8] = &[0x10, 0x00, 0x00, 0x00, 0x01, 0x03, 0xAB, 0xCD, 0xEF];
+            assert_eq!(Request::try_from(data), Err(Error::ByteCount(3)));
+            let data: &[u8] = &[
+                0x17, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x02, 0xAB, 0xCD,
+            ];
+            assert_eq!(Request::try_from(data), Err(Error::ByteCount(2)));
+        }
+
+        #[test]
+        fn read_with_address_overflow() {
+            let data: &[u8] = &[0x03, 0xFF, 0xFF, 0x00, 0x02];
+            assert_eq!(
+                Request::try_from(data),
+                Err(Error::AddressOverflow(0xFFFF, 2))
+            );
+            let data: &[u8] = &[0x03, 0xFF, 0xFF, 0x00, 0x01];
+            assert!(Request::try_from(data).is_ok());
+            assert_eq!(
+                Error::AddressOverflow(0xFFFF, 2).exception(),
+                Some(Exception::IllegalDataAddress)
+            );
+        }
+
+        #[test]
+        fn write_with_invalid_quantity() {
+            let data: &[u8] = &[0x0F, 0x00, 0x00, 0x07, 0xB1, 0x00];
+            assert_eq!(Request::try_from(data), Err(Error::Quantity(1969)));
+            let data: &[u8] = &[0x10, 0x00, 0x00, 0x00, 0x00, 0x00];
+            assert_eq!(Request::try_from(data), Err(Error::Quantity(0)));
+            let data: &[u8] = &[0x10, 0x00, 0x00, 0x00, 0x7C, 0xF8];
+            assert_eq!(Request::try_from(data), Err(Error::Quantity(124)));
+            let data: &[u8] = &[0x10, 0xFF, 0xFF, 0x00, 0x02, 0x04, 0, 0, 0, 0];
+            assert_eq!(
+                Request::try_from(data),
+                Err(Error::AddressOverflow(0xFFFF, 2))
+            );
+            let data: &[u8] = &[
+                0x17, 0x00, 0x00, 0x00, 0x7E, 0x00, 0x00, 0x00, 0x01, 0x02, 0, 0,
+            ];
+            assert_eq!(Request::try_from(data), Err(Error::Quantity(126)));
+            let data: &[u8] = &[0x17, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x7A, 0xF4];
+            assert_eq!(Request::try_from(data), Err(Error::Quantity(122)));
+        }
+
+        #[test]
+        fn read_discrete_inputs() {
+            let data: &[u8] = &[2, 0x00, 0x03, 0x00, 19];
+            let req = Request::try_from(data).unwrap();
+            assert_eq!(req, Request::ReadDiscreteInputs(0x03, 19));
+        }
+
+        #[test]
+        fn write_single_coil() {
+            let bytes: &[u8] = &[5, 0x12, 0x34, 0xFF, 0x00];
+            let req = Request::try_from(bytes).unwrap();
             assert_eq!(req, Request::WriteSingleCoil(0x1234, true));
         }
 
@@ -644,7 +1485,7 @@ mod tests {
             let data: &[u8] = &[
                 0x0F, 0x33, 0x11, 0x00, 0x04, 0x00, // byte count == 0
             ];
-            assert!(Request::try_from(data).is_ok());
+            assert_eq!(Request::try_from(data), Err(Error::ByteCount(0)));
 
             let bytes: &[u8] = &[0x0F, 0x33, 0x11, 0x00, 0x04, 0x01, 0b_0000_1101];
             let req = Request::try_from(bytes).unwrap();
@@ -732,6 +1573,129 @@ mod tests {
             };
         }
 
+        #[test]
+        fn mask_write_register() {
+            let bytes: &[u8] = &[0x16, 0x00, 0x04, 0x00, 0xF2, 0x00];
+            assert!(Request::try_from(bytes).is_err());
+            let bytes: &[u8] = &[0x16, 0x00, 0x04, 0x00, 0xF2, 0x00, 0x25];
+            let req = Request::try_from(bytes).unwrap();
+            assert_eq!(req, Request::MaskWriteRegister(0x04, 0x00F2, 0x0025));
+        }
+
+        #[test]
+        fn read_fifo_queue() {
+            let bytes: &[u8] = &[0x18, 0x04];
+            assert!(Request::try_from(bytes).is_err());
+            let bytes: &[u8] = &[0x18, 0x04, 0xDE];
+            let req = Request::try_from(bytes).unwrap();
+            assert_eq!(req, Request::ReadFifoQueue(0x04DE));
+        }
+
+        #[test]
+        fn read_file_record() {
+            let bytes: &[u8] = &[
+                0x14, 0x0E, 0x06, 0x00, 0x04, 0x00, 0x01, 0x00, 0x02, 0x06, 0x00, 0x03, 0x00, 0x09,
+                0x00, 0x02,
+            ];
+            let req = Request::try_from(bytes).unwrap();
+            if let Request::ReadFileRecord(sub_requests) = req {
+                let mut iter = sub_requests.into_iter();
+                assert_eq!(
+                    iter.next(),
+                    Some(FileSubRequest {
+                        file: 4,
+                        record: 1,
+                        length: 2
+                    })
+                );
+                assert_eq!(
+                    iter.next(),
+                    Some(FileSubRequest {
+                        file: 3,
+                        record: 9,
+                        length: 2
+                    })
+                );
+                assert_eq!(iter.next(), None);
+            } else {
+                unreachable!()
+            };
+        }
+
+        #[test]
+        fn read_file_record_with_invalid_sub_requests() {
+            let bytes: &[u8] = &[0x14, 0x07, 0x06, 0x00, 0x04, 0x00, 0x01, 0x00];
+            assert_eq!(
+                Request::try_from(bytes).err().unwrap(),
+                Error::ByteCount(0x07)
+            );
+            let bytes: &[u8] = &[0x14, 0x06, 0x06, 0x00, 0x04, 0x00, 0x01, 0x00];
+            assert_eq!(
+                Request::try_from(bytes).err().unwrap(),
+                Error::ByteCount(0x06)
+            );
+            let bytes: &[u8] = &[0x14, 0x07, 0x07, 0x00, 0x04, 0x00, 0x01, 0x00, 0x02];
+            assert_eq!(
+                Request::try_from(bytes).err().unwrap(),
+                Error::ReferenceType(0x07)
+            );
+        }
+
+        #[test]
+        fn write_file_record() {
+            let bytes: &[u8] = &[
+                0x15, 0x0D, 0x06, 0x00, 0x04, 0x00, 0x07, 0x00, 0x03, 0x06, 0xAF, 0x04, 0xBE, 0x10,
+                0x0D,
+            ];
+            let req = Request::try_from(bytes).unwrap();
+            if let Request::WriteFileRecord(records) = req {
+                let mut iter = records.into_iter();
+                let record = iter.next().unwrap();
+                assert_eq!(record.file, 4);
+                assert_eq!(record.record, 7);
+                assert_eq!(record.data.len(), 3);
+                assert_eq!(record.data.get(2), Some(0x100D));
+                assert_eq!(iter.next(), None);
+            } else {
+                unreachable!()
+            };
+            let bytes: &[u8] = &[
+                0x15, 0x0D, 0x06, 0x00, 0x04, 0x00, 0x07, 0x00, 0x04, 0x06, 0xAF, 0x04, 0xBE, 0x10,
+                0x0D,
+            ];
+            assert_eq!(
+                Request::try_from(bytes).err().unwrap(),
+                Error::ByteCount(0x0D)
+            );
+        }
+
+        #[test]
+        fn read_device_identification() {
+            let bytes: &[u8] = &[0x2B, 0x0E, 0x01];
+            assert!(Request::try_from(bytes).is_err());
+            let bytes: &[u8] = &[0x2B, 0x0E, 0x05, 0x00];
+            assert_eq!(
+                Request::try_from(bytes).err().unwrap(),
+                Error::ReadDeviceIdCode(0x05)
+            );
+            let bytes: &[u8] = &[0x2B, 0x0E, 0x04, 0x02];
+            let req = Request::try_from(bytes).unwrap();
+            assert_eq!(
+                req,
+                Request::ReadDeviceIdentification(ReadDeviceIdCode::Specific, 0x02)
+            );
+        }
+
+        #[test]
+        fn other_encapsulated_interface_transport() {
+            let bytes: &[u8] = &[0x2B, 0x0D, 0x01, 0x02];
+            let req = Request::try_from(bytes).unwrap();
+            assert_eq!(
+                req,
+                Request::Custom(FnCode::EncapsulatedInterfaceTransport, &[0x0D, 0x01, 0x02])
+            );
+        }
+
         #[test]
         fn custom() {
             let bytes: &[u8] = &[0x55, 0xCC, 0x88, 0xAA, 0xFF];
@@ -866,6 +1830,84 @@ mod tests {
             assert_eq!(bytes[3], 0x34);
         }
 
+        #[test]
+        fn mask_write_register() {
+            let res = Response::MaskWriteRegister(0x04, 0x00F2, 0x0025);
+            let bytes = &mut [0; 7];
+            res.encode(bytes).unwrap();
+            assert_eq!(bytes[0], 0x16);
+            assert_eq!(bytes[1], 0x00);
+            assert_eq!(bytes[2], 0x04);
+            assert_eq!(bytes[3], 0x00);
+            assert_eq!(bytes[4], 0xF2);
+            assert_eq!(bytes[5], 0x00);
+            assert_eq!(bytes[6], 0x25);
+        }
+
+        #[test]
+        fn read_fifo_queue() {
+            let buf: &mut [u8] = &mut [0; 4];
+            let res = Response::ReadFifoQueue(Data::from_words(&[0x01B8, 0x1284], buf).unwrap());
+            let bytes = &mut [0; 9];
+            res.encode(bytes).unwrap();
+            assert_eq!(bytes[0], 0x18);
+            assert_eq!(bytes[1], 0x00);
+            assert_eq!(bytes[2], 0x06);
+            assert_eq!(bytes[3], 0x00);
+            assert_eq!(bytes[4], 0x02);
+            assert_eq!(bytes[5], 0x01);
+            assert_eq!(bytes[6], 0xB8);
+            assert_eq!(bytes[7], 0x12);
+            assert_eq!(bytes[8], 0x84);
+        }
+
+        #[test]
+        fn read_fifo_queue_with_too_many_registers() {
+            let buf: &mut [u8] = &mut [0; 64];
+            let res = Response::ReadFifoQueue(Data::from_words(&[0; 32], buf).unwrap());
+            let bytes = &mut [0; 69];
+            assert_eq!(res.encode(bytes).err().unwrap(), Error::FifoCount(32));
+        }
+
+        #[test]
+        fn read_file_record() {
+            let buf = &mut [0; 4];
+            let a = Data::from_words(&[0x0DFE, 0x0020], buf).unwrap();
+            let buf = &mut [0; 6];
+            let b = Data::from_words(&[0x33CD, 0x0040, 0x0001], buf).unwrap();
+            let buf = &mut [0; 14];
+            let sub_responses = FileSubResponses::from_records(&[a, b], buf).unwrap();
+            let res = Response::ReadFileRecord(sub_responses);
+            let bytes = &mut [0; 16];
+            res.encode(bytes).unwrap();
+            assert_eq!(
+                bytes,
+                &[
+                    0x14, 0x0E, 0x05, 0x06, 0x0D, 0xFE, 0x00, 0x20, 0x07, 0x06, 0x33, 0xCD, 0x00,
+                    0x40, 0x00, 0x01
+                ]
+            );
+        }
+
+        #[test]
+        fn read_device_identification() {
+            let buf = &mut [0; 10];
+            let mut builder = DeviceIdentificationBuilder::new(ReadDeviceIdCode::Basic, 0x01, buf);
+            builder.push(0x00, b"Foo");
+            builder.push(0x01, b"Bar");
+            builder.push(0x02, b"V1.0");
+            let res = Response::ReadDeviceIdentification(builder.build());
+            let bytes = &mut [0; 17];
+            res.encode(bytes).unwrap();
+            assert_eq!(
+                bytes,
+                &[
+                    0x2B, 0x0E, 0x01, 0x01, 0xFF, 0x02, 0x02, 0x00, 0x03, b'F', b'o', b'o', 0x01,
+                    0x03, b'B', b'a', b'r'
+                ]
+            );
+        }
+
         #[test]
         fn custom() {
             let res = Response::Custom(FnCode::Custom(0x55), &[0xCC, 0x88, 0xAA, 0xFF]);
@@ -1005,6 +2047,119 @@ mod tests {
             assert!(Response::try_from(broken_bytes).is_err());
         }
 
+        #[test]
+        fn mask_write_register() {
+            let bytes: &[u8] = &[0x16, 0x00, 0x04, 0x00, 0xF2, 0x00, 0x25];
+            let rsp = Response::try_from(bytes).unwrap();
+            assert_eq!(rsp, Response::MaskWriteRegister(0x04, 0x00F2, 0x0025));
+            let broken_bytes: &[u8] = &[0x16, 0x00, 0x04, 0x00, 0xF2, 0x00];
+            assert!(Response::try_from(broken_bytes).is_err());
+        }
+
+        #[test]
+        fn read_fifo_queue() {
+            let bytes: &[u8] = &[0x18, 0x00, 0x06, 0x00, 0x02, 0x01, 0xB8, 0x12, 0x84];
+            let rsp = Response::try_from(bytes).unwrap();
+            assert_eq!(
+                rsp,
+                Response::ReadFifoQueue(Data {
+                    quantity: 2,
+                    data: &[0x01, 0xB8, 0x12, 0x84]
+                })
+            );
+            let broken_bytes: &[u8] = &[0x18, 0x00, 0x06, 0x00, 0x02, 0x01, 0xB8, 0x12];
+            assert!(Response::try_from(broken_bytes).is_err());
+        }
+
+        #[test]
+        fn read_empty_fifo_queue() {
+            let bytes: &[u8] = &[0x18, 0x00, 0x02, 0x00, 0x00];
+            let rsp = Response::try_from(bytes).unwrap();
+            assert_eq!(
+                rsp,
+                Response::ReadFifoQueue(Data {
+                    quantity: 0,
+                    data: &[]
+                })
+            );
+        }
+
+        #[test]
+        fn read_fifo_queue_with_invalid_counts() {
+            let bytes: &[u8] = &[0x18, 0x00, 0x06, 0x00, 0x03, 0x01, 0xB8, 0x12, 0x84];
+            assert_eq!(
+                Response::try_from(bytes).err().unwrap(),
+                Error::LengthMismatch(6, 8)
+            );
+            let bytes: &[u8] = &[0x18, 0x00, 0x42, 0x00, 0x20];
+            assert_eq!(
+                Response::try_from(bytes).err().unwrap(),
+                Error::FifoCount(32)
+            );
+        }
+
+        #[test]
+        fn read_file_record() {
+            let bytes: &[u8] = &[
+                0x14, 0x0C, 0x05, 0x06, 0x0D, 0xFE, 0x00, 0x20, 0x05, 0x06, 0x33, 0xCD, 0x00, 0x40,
+            ];
+            let rsp = Response::try_from(bytes).unwrap();
+            if let Response::ReadFileRecord(sub_responses) = rsp {
+                let mut iter = sub_responses.into_iter();
+                let a = iter.next().unwrap();
+                assert_eq!(a.get(0), Some(0x0DFE));
+                assert_eq!(a.get(1), Some(0x0020));
+                let b = iter.next().unwrap();
+                assert_eq!(b.get(0), Some(0x33CD));
+                assert_eq!(b.get(1), Some(0x0040));
+                assert_eq!(iter.next(), None);
+            } else {
+                unreachable!()
+            };
+            let broken_bytes: &[u8] = &[
+                0x14, 0x0C, 0x05, 0x06, 0x0D, 0xFE, 0x00, 0x20, 0x06, 0x06, 0x33, 0xCD, 0x00, 0x40,
+            ];
+            assert!(Response::try_from(broken_bytes).is_err());
+        }
+
+        #[test]
+        fn write_file_record() {
+            let bytes: &[u8] = &[
+                0x15, 0x0D, 0x06, 0x00, 0x04, 0x00, 0x07, 0x00, 0x03, 0x06, 0xAF, 0x04, 0xBE, 0x10,
+                0x0D,
+            ];
+            let rsp = Response::try_from(bytes).unwrap();
+            assert_eq!(
+                rsp,
+                Response::WriteFileRecord(FileRecords { data: &bytes[2..] })
+            );
+        }
+
+        #[test]
+        fn read_device_identification() {
+            let bytes: &[u8] = &[
+                0x2B, 0x0E, 0x01, 0x01, 0x00, 0x00, 0x03, 0x00, 0x03, b'F', b'o', b'o', 0x01, 0x03,
+                b'B', b'a', b'r', 0x02, 0x04, b'V', b'1', b'.', b'0',
+            ];
+            let rsp = Response::try_from(bytes).unwrap();
+            if let Response::ReadDeviceIdentification(dev_id) = rsp {
+                assert_eq!(dev_id.read_device_id_code, ReadDeviceIdCode::Basic);
+                assert_eq!(dev_id.conformity_level, 0x01);
+                assert!(!dev_id.more_follows);
+                assert_eq!(dev_id.next_object_id, 0x00);
+                assert_eq!(dev_id.len(), 3);
+                let mut objects = dev_id.objects();
+                assert_eq!(objects.next(), Some((0x00, &b"Foo"[..])));
+                assert_eq!(objects.next(), Some((0x01, &b"Bar"[..])));
+                assert_eq!(objects.next(), Some((0x02, &b"V1.0"[..])));
+                assert_eq!(objects.next(), None);
+            } else {
+                unreachable!()
+            }
+            let broken_bytes = &bytes[..bytes.len() - 1];
+            assert!(Response::try_from(broken_bytes).is_err());
+        }
+
         #[test]
         fn custom() {
             let bytes: &[u8] = &[0x55, 0xCC, 0x88, 0xAA, 0xFF];
@@ -1018,4 +2173,400 @@ mod tests {
             assert_eq!(rsp, Response::Custom(FnCode::Custom(0x66), &[]));
         }
     }
+
+    mod deserialize_responses_for_requests {
+        use super::*;
+
+        #[test]
+        fn read_coils() {
+            let req = Request::ReadCoils(0x12, 5);
+            let bytes: &[u8] = &[1, 1, 0b_1110_1001];
+            let rsp = Response::decode_for(&req, bytes).unwrap();
+            assert_eq!(
+                rsp,
+                Response::ReadCoils(Coils {
+                    quantity: 5,
+                    data: &[0b_1110_1001]
+                })
+            );
+            if let Response::ReadCoils(coils) = rsp {
+                assert_eq!(coils.len(), 5);
+                assert_eq!(coils.into_iter().count(), 5);
+            } else {
+                unreachable!()
+            }
+        }
+
+        #[test]
+        fn read_discrete_inputs() {
+            let req = Request::ReadDiscreteInputs(0x12, 9);
+            let bytes: &[u8] = &[2, 2, 0xFF, 0x01];
+            let rsp = Response::decode_for(&req, bytes).unwrap();
+            assert_eq!(
+                rsp,
+                Response::ReadDiscreteInputs(Coils {
+                    quantity: 9,
+                    data: &[0xFF, 0x01]
+                })
+            );
+        }
+
+        #[test]
+        fn read_coils_with_invalid_byte_count() {
+            let req = Request::ReadCoils(0x12, 9);
+            let bytes: &[u8] = &[1, 1, 0xFF];
+            assert_eq!(
+                Response::decode_for(&req, bytes).err().unwrap(),
+                Error::ByteCount(1)
+            );
+        }
+
+        #[test]
+        fn read_holding_registers() {
+            let req = Request::ReadHoldingRegisters(0x12, 2);
+            let bytes: &[u8] = &[3, 0x04, 0xAA, 0x00, 0x11, 0x11];
+            let rsp = Response::decode_for(&req, bytes).unwrap();
+            assert_eq!(
+                rsp,
+                Response::ReadHoldingRegisters(Data {
+                    quantity: 2,
+                    data: &[0xAA, 0x00, 0x11, 0x11]
+                })
+            );
+            let req = Request::ReadHoldingRegisters(0x12, 3);
+            assert_eq!(
+                Response::decode_for(&req, bytes).err().unwrap(),
+                Error::ByteCount(4)
+            );
+        }
+
+        #[test]
+        fn read_write_multiple_registers() {
+            let buf = &mut [0; 2];
+            let data = Data::from_words(&[0xABCD], buf).unwrap();
+            let req = Request::ReadWriteMultipleRegisters(0x05, 1, 0x03, data);
+            let bytes: &[u8] = &[0x17, 0x02, 0x12, 0x34];
+            assert!(Response::decode_for(&req, bytes).is_ok());
+            let req = Request::ReadWriteMultipleRegisters(0x05, 2, 0x03, data);
+            assert!(Response::decode_for(&req, bytes).is_err());
+        }
+
+        #[test]
+        fn write_single_coil() {
+            let bytes: &[u8] = &[5, 0x00, 0x33];
+            let req = Request::WriteSingleCoil(0x33, true);
+            assert!(Response::decode_for(&req, bytes).is_ok());
+            let req = Request::WriteSingleCoil(0x34, true);
+            assert_eq!(
+                Response::decode_for(&req, bytes).err().unwrap(),
+                Error::AddressMismatch(0x34, 0x33)
+            );
+        }
+
+        #[test]
+        fn write_multiple_coils() {
+            let buf = &mut [0];
+            let coils = Coils::from_bools(&[true; 5], buf).unwrap();
+            let bytes: &[u8] = &[0x0F, 0x33, 0x11, 0x00, 0x05];
+            let req = Request::WriteMultipleCoils(0x3311, coils);
+            assert!(Response::decode_for(&req, bytes).is_ok());
+            let req = Request::WriteMultipleCoils(0x3312, coils);
+            assert_eq!(
+                Response::decode_for(&req, bytes).err().unwrap(),
+                Error::AddressMismatch(0x3312, 0x3311)
+            );
+            let bytes: &[u8] = &[0x0F, 0x33, 0x11, 0x00, 0x04];
+            let req = Request::WriteMultipleCoils(0x3311, coils);
+            assert_eq!(
+                Response::decode_for(&req, bytes).err().unwrap(),
+                Error::QuantityMismatch(5, 4)
+            );
+        }
+
+        #[test]
+        fn write_single_register() {
+            let bytes: &[u8] = &[6, 0x00, 0x07, 0xAB, 0xCD];
+            let req = Request::WriteSingleRegister(0x07, 0xABCD);
+            assert!(Response::decode_for(&req, bytes).is_ok());
+            let req = Request::WriteSingleRegister(0x07, 0xABCE);
+            assert_eq!(
+                Response::decode_for(&req, bytes).err().unwrap(),
+                Error::ValueMismatch(0xABCE, 0xABCD)
+            );
+        }
+
+        #[test]
+        fn write_multiple_registers() {
+            let buf = &mut [0; 4];
+            let data = Data::from_words(&[0xABCD, 0xEF12], buf).unwrap();
+            let bytes: &[u8] = &[0x10, 0x00, 0x06, 0x00, 0x02];
+            let req = Request::WriteMultipleRegisters(0x06, data);
+            assert!(Response::decode_for(&req, bytes).is_ok());
+            let req = Request::WriteMultipleRegisters(0x07, data);
+            assert_eq!(
+                Response::decode_for(&req, bytes).err().unwrap(),
+                Error::AddressMismatch(0x07, 0x06)
+            );
+        }
+
+        #[test]
+        fn mismatching_function_code() {
+            let req = Request::ReadInputRegisters(0x12, 2);
+            let bytes: &[u8] = &[3, 0x04, 0xAA, 0x00, 0x11, 0x11];
+            assert_eq!(
+                Response::decode_for(&req, bytes).err().unwrap(),
+                Error::FnCode(3)
+            );
+        }
+    }
+
+    #[cfg(feature = "rtu")]
+    mod serial_line_functions {
+        use super::*;
+
+        fn assert_request_roundtrip(req: Request, expected: &[u8]) {
+            let bytes = &mut [0; 32];
+            let len = req.encode(bytes).unwrap();
+            assert_eq!(len, req.pdu_len());
+            assert_eq!(&bytes[..len], expected);
+            assert_eq!(Request::try_from(&bytes[..len]).unwrap(), req);
+        }
+
+        fn assert_response_roundtrip(rsp: Response, expected: &[u8]) {
+            let bytes = &mut [0; 32];
+            let len = rsp.encode(bytes).unwrap();
+            assert_eq!(len, rsp.pdu_len());
+            assert_eq!(&bytes[..len], expected);
+            assert_eq!(Response::try_from(&bytes[..len]).unwrap(), rsp);
+        }
+
+        #[test]
+        fn read_exception_status() {
+            assert_request_roundtrip(Request::ReadExceptionStatus, &[0x07]);
+            assert_response_roundtrip(Response::ReadExceptionStatus(0x6D), &[0x07, 0x6D]);
+            let broken_bytes: &[u8] = &[0x07];
+            assert!(Response::try_from(broken_bytes).is_err());
+        }
+
+        #[test]
+        fn diagnostics() {
+            let buf = &mut [0; 2];
+            let data = Data::from_words(&[0xA537], buf).unwrap();
+            assert_request_roundtrip(
+                Request::Diagnostics(DiagnosticSubFunction::ReturnQueryData, data),
+                &[0x08, 0x00, 0x00, 0xA5, 0x37],
+            );
+            assert_response_roundtrip(
+                Response::Diagnostics(DiagnosticSubFunction::ReturnQueryData, data),
+                &[0x08, 0x00, 0x00, 0xA5, 0x37],
+            );
+            let broken_bytes: &[u8] = &[0x08, 0x00, 0x00, 0xA5];
+            assert_eq!(
+                Request::try_from(broken_bytes).err().unwrap(),
+                Error::ByteCount(1)
+            );
+            assert!(Response::try_from(broken_bytes).is_err());
+        }
+
+        #[test]
+        fn diagnostics_with_invalid_data() {
+            let bytes: &[u8] = &[0x08, 0x00, 0x0B, 0x00, 0x00];
+            assert_eq!(
+                Request::try_from(bytes).unwrap(),
+                Request::Diagnostics(
+                    DiagnosticSubFunction::ReturnBusMessageCount,
+                    Data {
+                        quantity: 1,
+                        data: &[0x00, 0x00]
+                    }
+                )
+            );
+            let bytes: &[u8] = &[0x08, 0x00, 0x0B, 0x00, 0x01];
+            assert_eq!(
+                Request::try_from(bytes).err().unwrap(),
+                Error::DiagnosticsData(0x0001)
+            );
+            let bytes: &[u8] = &[0x08, 0x00, 0x01, 0x12, 0x00];
+            assert_eq!(
+                Request::try_from(bytes).err().unwrap(),
+                Error::DiagnosticsData(0x1200)
+            );
+        }
+
+        #[test]
+        fn get_comm_event_counter() {
+            assert_request_roundtrip(Request::GetCommEventCounter, &[0x0B]);
+            assert_response_roundtrip(
+                Response::GetCommEventCounter(0xFFFF, 0x0108),
+                &[0x0B, 0xFF, 0xFF, 0x01, 0x08],
+            );
+        }
+
+        #[test]
+        fn get_comm_event_log() {
+            assert_request_roundtrip(Request::GetCommEventLog, &[0x0C]);
+            assert_response_roundtrip(
+                Response::GetCommEventLog(0x0000, 0x0108, 0x0121, &[0x20, 0x00]),
+                &[0x0C, 0x08, 0x00, 0x00, 0x01, 0x08, 0x01, 0x21, 0x20, 0x00],
+            );
+            let broken_bytes: &[u8] = &[0x0C, 0x05, 0x00, 0x00, 0x01, 0x08, 0x01, 0x21];
+            assert_eq!(
+                Response::try_from(broken_bytes).err().unwrap(),
+                Error::ByteCount(5)
+            );
+            let broken_bytes: &[u8] = &[0x0C, 0x08, 0x00, 0x00, 0x01, 0x08, 0x01, 0x21, 0x20];
+            assert!(Response::try_from(broken_bytes).is_err());
+        }
+
+        #[test]
+        fn report_server_id() {
+            assert_request_roundtrip(Request::ReportServerId, &[0x11]);
+            assert_response_roundtrip(
+                Response::ReportServerId(&[0x42, 0x43], true),
+                &[0x11, 0x03, 0x42, 0x43, 0xFF],
+            );
+            assert_response_roundtrip(Response::ReportServerId(&[], false), &[0x11, 0x01, 0x00]);
+            let broken_bytes: &[u8] = &[0x11, 0x00, 0x00];
+            assert_eq!(
+                Response::try_from(broken_bytes).err().unwrap(),
+                Error::ByteCount(0)
+            );
+        }
+    }
+
+    mod fuzz {
+        use super::*;
+
+        /// Simple xorshift generator to get reproducible input.
+        struct Rng(u32);
+
+        impl Rng {
+            fn next(&mut self) -> u32 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 17;
+                self.0 ^= self.0 << 5;
+                self.0
+            }
+            /// Random bytes that prefer small values to hit valid counts.
+            fn fill(&mut self, buf: &mut [u8]) {
+                for b in buf.iter_mut() {
+                    let r = self.next();
+                    *b = if r & 0x100 == 0 {
+                        (r & 0x0F) as u8
+                    } else {
+                        r as u8
+                    };
+                }
+            }
+        }
+
+        fn touch_coils(coils: Coils) {
+            for idx in 0..=coils.len() {
+                let _ = coils.get(idx);
+            }
+            assert_eq!(coils.into_iter().count(), coils.len());
+        }
+
+        fn touch_data(data: Data) {
+            for idx in 0..=data.len() {
+                let _ = data.get(idx);
+            }
+            assert_eq!(data.into_iter().count(), data.len());
+        }
+
+        fn touch_request(req: Request) {
+            match req {
+                Request::WriteMultipleCoils(_, coils) => touch_coils(coils),
+                Request::WriteMultipleRegisters(_, data)
+                | Request::ReadWriteMultipleRegisters(_, _, _, data) => touch_data(data),
+                Request::ReadFileRecord(sub_requests) => sub_requests.into_iter().for_each(drop),
+                Request::WriteFileRecord(records) => {
+                    records.into_iter().for_each(|r| touch_data(r.data))
+                }
+                #[cfg(feature = "rtu")]
+                Request::Diagnostics(_, data) => touch_data(data),
+                _ => {}
+            }
+            let buf = &mut [0; 512];
+            let len = req.encode(buf).unwrap();
+            assert_eq!(len, req.pdu_len());
+        }
+
+        fn touch_response(rsp: Response) {
+            match rsp {
+                Response::ReadCoils(coils) | Response::ReadDiscreteInputs(coils) => {
+                    touch_coils(coils)
+                }
+                Response::ReadInputRegisters(data)
+                | Response::ReadHoldingRegisters(data)
+                | Response::ReadWriteMultipleRegisters(data)
+                | Response::ReadFifoQueue(data) => touch_data(data),
+                Response::ReadFileRecord(sub_responses) => {
+                    sub_responses.into_iter().for_each(touch_data)
+                }
+                Response::WriteFileRecord(records) => {
+                    records.into_iter().for_each(|r| touch_data(r.data))
+                }
+                Response::ReadDeviceIdentification(id) => id.objects().for_each(drop),
+                #[cfg(feature = "rtu")]
+                Response::Diagnostics(_, data) => touch_data(data),
+                _ => {}
+            }
+            let buf = &mut [0; 512];
+            let len = rsp.encode(buf).unwrap();
+            assert_eq!(len, rsp.pdu_len());
+        }
+
+        #[test]
+        fn decode_random_pdus() {
+            let requests = [
+                Request::ReadCoils(0, 9),
+                Request::ReadHoldingRegisters(0, 3),
+                Request::WriteMultipleCoils(0, Coils::new(&[0x01], 1).unwrap()),
+            ];
+            let mut rng = Rng(0x1234_5678);
+            let buf = &mut [0; 300];
+            for i in 0..200_000 {
+                let len = match rng.next() % 8 {
+                    0 => rng.next() as usize % buf.len(),
+                    _ => rng.next() as usize % 24,
+                };
+                let bytes = &mut buf[..len];
+                rng.fill(bytes);
+                if let Some(fn_code) = bytes.first_mut() {
+                    *fn_code = (i % 0x30) as u8;
+                }
+                let bytes = &*bytes;
+                if let Ok(req) = Request::try_from(bytes) {
+                    touch_request(req);
+                }
+                if let Ok(rsp) = Response::try_from(bytes) {
+                    touch_response(rsp);
+                }
+                for req in &requests {
+                    if let Ok(rsp) = Response::decode_for(req, bytes) {
+                        touch_response(rsp);
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn decode_multi_write_requests_with_random_counts() {
+            let mut rng = Rng(0x9ABC_DEF0);
+            let buf = &mut [0; 300];
+            for _ in 0..100_000 {
+                let fn_code = [0x0F, 0x10, 0x17][rng.next() as usize % 3];
+                let len = rng.next() as usize % buf.len();
+                let bytes = &mut buf[..len];
+                rng.fill(bytes);
+                if let Some(b) = bytes.first_mut() {
+                    *b = fn_code;
+                }
+                if let Ok(req) = Request::try_from(&*bytes) {
+                    touch_request(req);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,137 @@
+//! Modbus RTU client (master) specific functions.
+use super::*;
+
+/// Encode an RTU request.
+pub fn encode_request(adu: RequestAdu, buf: &mut [u8]) -> Result<usize> {
+    let RequestAdu { hdr, pdu } = adu;
+    if buf.len() < 2 {
+        return Err(Error::BufferSize);
+    }
+    let len = pdu.encode(&mut buf[1..])?;
+    if buf.len() < len + 3 {
+        return Err(Error::BufferSize);
+    }
+    buf[0] = hdr.slave;
+    let crc = crc16(&buf[0..=len]);
+    BigEndian::write_u16(&mut buf[len + 1..], crc);
+    Ok(len + 3)
+}
+
+/// Decode an RTU response.
+pub fn decode_response(buf: &[u8]) -> Result<Option<ResponseAdu<'_>>> {
+    decode(DecoderType::Response, buf).and_then(|frame| {
+        if let Some((DecodedFrame { slave, pdu }, _frame_pos)) = frame {
+            let hdr = Header { slave };
+            ResponsePdu::try_from(pdu).map(|pdu| Some(ResponseAdu { hdr, pdu }))
+        } else {
+            Ok(None)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_read_holding_registers_request() {
+        let adu = RequestAdu {
+            hdr: Header { slave: 0x01 },
+            pdu: RequestPdu(Request::ReadHoldingRegisters(0x082B, 2)),
+        };
+        let buf = &mut [0; 100];
+        let len = encode_request(adu, buf).unwrap();
+        assert_eq!(len, 8);
+        assert_eq!(buf[0], 0x01);
+        assert_eq!(buf[1], 0x03);
+        assert_eq!(buf[2], 0x08);
+        assert_eq!(buf[3], 0x2B);
+        assert_eq!(buf[4], 0x00);
+        assert_eq!(buf[5], 0x02);
+        assert_eq!(buf[6], 0xB6);
+        assert_eq!(buf[7], 0x63);
+    }
+
+    #[test]
+    fn encode_request_with_too_small_buffer() {
+        let adu = RequestAdu {
+            hdr: Header { slave: 0x01 },
+            pdu: RequestPdu(Request::ReadHoldingRegisters(0x082B, 2)),
+        };
+        assert!(encode_request(adu, &mut [0; 7]).is_err());
+    }
+
+    #[test]
+    fn decode_empty_response() {
+        let rsp = decode_response(&[]).unwrap();
+        assert!(rsp.is_none());
+    }
+
+    #[test]
+    fn decode_partly_received_response() {
+        let buf = &[
+            0x01, // slave address
+            0x03, // function code
+            0x04, // byte count
+            0x89, //
+        ];
+        let rsp = decode_response(buf).unwrap();
+        assert!(rsp.is_none());
+    }
+
+    #[test]
+    fn decode_read_holding_registers_response() {
+        let buf = &[
+            0x01, // slave address
+            0x03, // function code
+            0x04, // byte count
+            0x89, //
+            0x02, //
+            0x42, //
+            0xC7, //
+            0x00, // crc
+            0x9D, // crc
+        ];
+        let ResponseAdu { hdr, pdu } = decode_response(buf).unwrap().unwrap();
+        assert_eq!(hdr.slave, 0x01);
+        let ResponsePdu(rsp) = pdu;
+        if let Response::ReadHoldingRegisters(data) = rsp.unwrap() {
+            assert_eq!(data.get(0), Some(0x8902));
+            assert_eq!(data.get(1), Some(0x42C7));
+            assert_eq!(data.get(2), None);
+        } else {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn decode_exception_response() {
+        let buf = &[
+            0x12, // slave address
+            0x83, // exception function code
+            0x02, // exception code
+            0x31, // crc
+            0x34, // crc
+        ];
+        let ResponseAdu { hdr, pdu } = decode_response(buf).unwrap().unwrap();
+        assert_eq!(hdr.slave, 0x12);
+        assert_eq!(
+            pdu,
+            ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::ReadHoldingRegisters,
+                exception: Exception::IllegalDataAddress,
+            }))
+        );
+    }
+
+    #[test]
+    fn decode_response_encoded_by_server() {
+        let rsp_adu = ResponseAdu {
+            hdr: Header { slave: 0x05 },
+            pdu: ResponsePdu(Ok(Response::WriteSingleRegister(0x2222, 0xABCD))),
+        };
+        let buf = &mut [0; 100];
+        let len = server::encode_response(rsp_adu, buf).unwrap();
+        assert_eq!(decode_response(&buf[..len]).unwrap().unwrap(), rsp_adu);
+    }
+}
@@ -0,0 +1,584 @@
+//! Server (slave) side diagnostic counters and communication event log.
+use super::*;
+
+// [MODBUS Application Protocol Specification V1.1b3](http://modbus.org/docs/Modbus_Application_Protocol_V1_1b3.pdf), page 27
+// "The remote device stores up to 64 events in the log"
+const MAX_EVENTS: usize = 64;
+
+/// Broadcast address.
+const BROADCAST: SlaveId = 0;
+
+// Receive event bits
+const EV_RECEIVE: u8 = 0x80;
+const EV_RECEIVE_COMM_ERROR: u8 = 0x02;
+const EV_RECEIVE_OVERRUN: u8 = 0x10;
+const EV_RECEIVE_LISTEN_ONLY: u8 = 0x20;
+const EV_RECEIVE_BROADCAST: u8 = 0x40;
+
+// Send event bits
+const EV_SEND: u8 = 0x40;
+const EV_SEND_READ_EXCEPTION: u8 = 0x01;
+const EV_SEND_ABORT_EXCEPTION: u8 = 0x02;
+const EV_SEND_BUSY_EXCEPTION: u8 = 0x04;
+const EV_SEND_NAK_EXCEPTION: u8 = 0x08;
+
+// Other events
+const EV_LISTEN_ONLY_MODE: u8 = 0x04;
+const EV_COMM_RESTART: u8 = 0x00;
+
+/// The "busy" status of a comm event counter or log response.
+const STATUS_BUSY: u16 = 0xFFFF;
+
+/// Exception code of a negative acknowledge.
+const NAK_EXCEPTION: u8 = 0x07;
+
+/// What a server has to do with a received request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disposition {
+    /// The request has to be processed by the application.
+    pub process: bool,
+    /// A response has to be sent.
+    ///
+    /// This is `false` for broadcast requests that have to be
+    /// processed without being answered.
+    pub respond: bool,
+}
+
+impl Disposition {
+    const fn new(process: bool, respond: bool) -> Self {
+        Self { process, respond }
+    }
+}
+
+/// The diagnostic state of an RTU server (slave).
+///
+/// The state has to be fed with the outcome of decoding requests and
+/// with the responses that have been sent. It then answers the
+/// diagnostics, comm event counter and comm event log requests.
+#[derive(Debug, Clone)]
+pub struct DiagnosticsState {
+    slave: SlaveId,
+    busy: bool,
+    listen_only: bool,
+    ascii_delimiter: u8,
+    diagnostic_register: Word,
+    bus_message_count: u16,
+    bus_communication_error_count: u16,
+    bus_exception_error_count: u16,
+    server_message_count: u16,
+    server_no_response_count: u16,
+    server_nak_count: u16,
+    server_busy_count: u16,
+    bus_character_overrun_count: u16,
+    comm_event_counter: u16,
+    events: [u8; MAX_EVENTS],
+    events_head: usize,
+    events_len: usize,
+}
+
+impl DiagnosticsState {
+    /// Create a new state for the server with the given slave ID.
+    pub const fn new(slave: SlaveId) -> Self {
+        Self {
+            slave,
+            busy: false,
+            listen_only: false,
+            ascii_delimiter: b'\n',
+            diagnostic_register: 0,
+            bus_message_count: 0,
+            bus_communication_error_count: 0,
+            bus_exception_error_count: 0,
+            server_message_count: 0,
+            server_no_response_count: 0,
+            server_nak_count: 0,
+            server_busy_count: 0,
+            bus_character_overrun_count: 0,
+            comm_event_counter: 0,
+            events: [0; MAX_EVENTS],
+            events_head: 0,
+            events_len: 0,
+        }
+    }
+    /// Returns `true` if the server is in listen only mode.
+    pub const fn listen_only(&self) -> bool {
+        self.listen_only
+    }
+    /// The current ASCII input delimiter.
+    pub const fn ascii_delimiter(&self) -> u8 {
+        self.ascii_delimiter
+    }
+    /// The current content of the diagnostic register.
+    pub const fn diagnostic_register(&self) -> Word {
+        self.diagnostic_register
+    }
+    /// Set the content of the device specific diagnostic register.
+    pub fn set_diagnostic_register(&mut self, register: Word) {
+        self.diagnostic_register = register;
+    }
+    /// Mark the server as busy processing a previous command.
+    pub fn set_busy(&mut self, busy: bool) {
+        self.busy = busy;
+    }
+    /// Get the value of a diagnostics counter.
+    ///
+    /// It returns `None` if the sub-function does not refer to a counter.
+    pub fn counter(&self, sub_fn: DiagnosticSubFunction) -> Option<u16> {
+        use DiagnosticSubFunction::*;
+
+        let cnt = match sub_fn {
+            ReturnBusMessageCount => self.bus_message_count,
+            ReturnBusCommunicationErrorCount => self.bus_communication_error_count,
+            ReturnBusExceptionErrorCount => self.bus_exception_error_count,
+            ReturnServerMessageCount => self.server_message_count,
+            ReturnServerNoResponseCount => self.server_no_response_count,
+            ReturnServerNakCount => self.server_nak_count,
+            ReturnServerBusyCount => self.server_busy_count,
+            ReturnBusCharacterOverrunCount => self.bus_character_overrun_count,
+            _ => return None,
+        };
+        Some(cnt)
+    }
+    /// The current value of the comm event counter.
+    pub const fn comm_event_counter(&self) -> u16 {
+        self.comm_event_counter
+    }
+    /// Copy the comm event log into a buffer (most recent event first).
+    ///
+    /// It returns the number of copied events.
+    pub fn copy_events_to(&self, buf: &mut [u8]) -> usize {
+        let cnt = self.events_len.min(buf.len());
+        for (i, b) in buf.iter_mut().take(cnt).enumerate() {
+            *b = self.events[(self.events_head + MAX_EVENTS - 1 - i) % MAX_EVENTS];
+        }
+        cnt
+    }
+
+    /// Record an error that occurred while decoding a frame.
+    ///
+    /// Only CRC and LRC errors are counted. A [`FrameDecoder`] reports
+    /// each frame that fails its checksum once.
+    pub fn record_decode_error(&mut self, err: &Error) {
+        if let Error::Crc(_, _) | Error::Lrc(_, _) = err {
+            self.bus_communication_error_count = self.bus_communication_error_count.wrapping_add(1);
+            self.push_event(EV_RECEIVE | EV_RECEIVE_COMM_ERROR | self.listen_only_bit());
+        }
+    }
+    /// Record a character overrun of the serial line.
+    pub fn record_overrun(&mut self) {
+        self.bus_character_overrun_count = self.bus_character_overrun_count.wrapping_add(1);
+        self.push_event(EV_RECEIVE | EV_RECEIVE_OVERRUN | self.listen_only_bit());
+    }
+    /// Record a successfully decoded request.
+    ///
+    /// It returns whether the request should be processed and
+    /// answered by the server.
+    pub fn record_request(&mut self, adu: &RequestAdu) -> Disposition {
+        use DiagnosticSubFunction as d;
+
+        self.bus_message_count = self.bus_message_count.wrapping_add(1);
+        let broadcast = adu.hdr.slave == BROADCAST;
+        if !broadcast && adu.hdr.slave != self.slave {
+            return Disposition::new(false, false);
+        }
+        self.server_message_count = self.server_message_count.wrapping_add(1);
+        let broadcast_bit = if broadcast { EV_RECEIVE_BROADCAST } else { 0 };
+        self.push_event(EV_RECEIVE | broadcast_bit | self.listen_only_bit());
+
+        let RequestPdu(req) = adu.pdu;
+        let disposition = match req {
+            Request::Diagnostics(d::RestartCommunicationsOption, data) if self.listen_only => {
+                // Only a restart brings the server back from listen only mode
+                // but no response is returned.
+                self.restart_communications(data.get(0) == Some(0xFF00));
+                Disposition::new(false, false)
+            }
+            _ if self.listen_only => Disposition::new(false, false),
+            Request::Diagnostics(d::ForceListenOnlyMode, _) => {
+                self.listen_only = true;
+                self.push_event(EV_LISTEN_ONLY_MODE);
+                Disposition::new(false, false)
+            }
+            _ => Disposition::new(true, !broadcast),
+        };
+        if !disposition.respond {
+            self.server_no_response_count = self.server_no_response_count.wrapping_add(1);
+        }
+        disposition
+    }
+    /// Record a response that has been sent.
+    pub fn record_response(&mut self, adu: &ResponseAdu) {
+        let ResponsePdu(rsp) = adu.pdu;
+        match rsp {
+            Ok(rsp) => {
+                if FnCode::from(rsp) != FnCode::GetCommEventCounter {
+                    self.comm_event_counter = self.comm_event_counter.wrapping_add(1);
+                }
+                self.push_event(EV_SEND);
+            }
+            Err(ex) => {
+                self.bus_exception_error_count = self.bus_exception_error_count.wrapping_add(1);
+                let code = u8::from(ex.exception);
+                let bit = match code {
+                    0x01..=0x03 => EV_SEND_READ_EXCEPTION,
+                    0x04 => EV_SEND_ABORT_EXCEPTION,
+                    0x05 | 0x06 => EV_SEND_BUSY_EXCEPTION,
+                    NAK_EXCEPTION => EV_SEND_NAK_EXCEPTION,
+                    _ => 0,
+                };
+                if ex.exception == Exception::ServerDeviceBusy {
+                    self.server_busy_count = self.server_busy_count.wrapping_add(1);
+                }
+                if code == NAK_EXCEPTION {
+                    self.server_nak_count = self.server_nak_count.wrapping_add(1);
+                }
+                self.push_event(EV_SEND | bit);
+            }
+        }
+    }
+
+    /// Answer a diagnostics, comm event counter or comm event log request.
+    ///
+    /// It returns `None` if the request has to be handled by the application.
+    pub fn reply<'a>(&mut self, req: &Request<'a>, buf: &'a mut [u8]) -> Option<ResponsePdu<'a>> {
+        use DiagnosticSubFunction as d;
+
+        let status = if self.busy { STATUS_BUSY } else { 0x0000 };
+        let rsp = match *req {
+            Request::GetCommEventCounter => {
+                Response::GetCommEventCounter(status, self.comm_event_counter)
+            }
+            Request::GetCommEventLog => {
+                let cnt = self.copy_events_to(buf);
+                Response::GetCommEventLog(
+                    status,
+                    self.comm_event_counter,
+                    self.bus_message_count,
+                    &buf[..cnt],
+                )
+            }
+            Request::Diagnostics(sub_fn, data) => {
+                let word = match sub_fn {
+                    d::ReturnQueryData => {
+                        return Some(ResponsePdu(Ok(Response::Diagnostics(sub_fn, data))))
+                    }
+                    d::RestartCommunicationsOption => {
+                        self.restart_communications(data.get(0) == Some(0xFF00));
+                        data.get(0).unwrap_or(0)
+                    }
+                    d::ReturnDiagnosticRegister => self.diagnostic_register,
+                    d::ChangeAsciiInputDelimiter => {
+                        let word = data.get(0).unwrap_or(0);
+                        self.ascii_delimiter = (word >> 8) as u8;
+                        word
+                    }
+                    d::ClearCountersAndDiagnosticRegister => {
+                        self.clear_counters();
+                        self.diagnostic_register = 0;
+                        0
+                    }
+                    d::ClearOverrunCounterAndFlag => {
+                        self.bus_character_overrun_count = 0;
+                        0
+                    }
+                    d::ForceListenOnlyMode | d::Custom(_) => return None,
+                    _ => self.counter(sub_fn)?,
+                };
+                let data = Data::from_words(&[word], buf).ok()?;
+                Response::Diagnostics(sub_fn, data)
+            }
+            _ => return None,
+        };
+        Some(ResponsePdu(Ok(rsp)))
+    }
+
+    fn restart_communications(&mut self, clear_log: bool) {
+        self.listen_only = false;
+        self.clear_counters();
+        if clear_log {
+            self.events_head = 0;
+            self.events_len = 0;
+        }
+        self.push_event(EV_COMM_RESTART);
+    }
+
+    fn clear_counters(&mut self) {
+        self.bus_message_count = 0;
+        self.bus_communication_error_count = 0;
+        self.bus_exception_error_count = 0;
+        self.server_message_count = 0;
+        self.server_no_response_count = 0;
+        self.server_nak_count = 0;
+        self.server_busy_count = 0;
+        self.bus_character_overrun_count = 0;
+        self.comm_event_counter = 0;
+    }
+
+    const fn listen_only_bit(&self) -> u8 {
+        if self.listen_only {
+            EV_RECEIVE_LISTEN_ONLY
+        } else {
+            0
+        }
+    }
+
+    fn push_event(&mut self, event: u8) {
+        self.events[self.events_head] = event;
+        self.events_head = (self.events_head + 1) % MAX_EVENTS;
+        self.events_len = (self.events_len + 1).min(MAX_EVENTS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use DiagnosticSubFunction as d;
+
+    fn request(slave: SlaveId, req: Request) -> RequestAdu {
+        RequestAdu {
+            hdr: Header { slave },
+            pdu: RequestPdu(req),
+        }
+    }
+
+    fn response(rsp: core::result::Result<Response, Exception>) -> ResponseAdu {
+        ResponseAdu {
+            hdr: Header { slave: 0x12 },
+            pdu: ResponsePdu(rsp.map_err(|exception| ExceptionResponse {
+                function: FnCode::ReadCoils,
+                exception,
+            })),
+        }
+    }
+
+    fn diagnostics(sub_fn: DiagnosticSubFunction, data: &'static [u8; 2]) -> Request<'static> {
+        Request::Diagnostics(sub_fn, Data { quantity: 1, data })
+    }
+
+    fn counter(state: &mut DiagnosticsState, sub_fn: DiagnosticSubFunction) -> u16 {
+        let buf = &mut [0; 2];
+        match state.reply(&diagnostics(sub_fn, &[0x00, 0x00]), buf) {
+            Some(ResponsePdu(Ok(Response::Diagnostics(rsp_sub_fn, data)))) => {
+                assert_eq!(rsp_sub_fn, sub_fn);
+                data.get(0).unwrap()
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn count_messages() {
+        let mut state = DiagnosticsState::new(0x12);
+        assert_eq!(
+            state.record_request(&request(0x12, Request::ReadCoils(0, 1))),
+            Disposition::new(true, true)
+        );
+        assert_eq!(
+            state.record_request(&request(0x13, Request::ReadCoils(0, 1))),
+            Disposition::new(false, false)
+        );
+        assert_eq!(
+            state.record_request(&request(0x00, Request::ReadCoils(0, 1))),
+            Disposition::new(true, false)
+        );
+        state.record_decode_error(&Error::Crc(0x1234, 0x4321));
+        state.record_decode_error(&Error::BufferSize);
+        state.record_overrun();
+
+        assert_eq!(counter(&mut state, d::ReturnBusMessageCount), 3);
+        assert_eq!(counter(&mut state, d::ReturnServerMessageCount), 2);
+        assert_eq!(counter(&mut state, d::ReturnServerNoResponseCount), 1);
+        assert_eq!(counter(&mut state, d::ReturnBusCommunicationErrorCount), 1);
+        assert_eq!(counter(&mut state, d::ReturnBusCharacterOverrunCount), 1);
+    }
+
+    #[test]
+    fn count_corrupted_frames_of_a_frame_decoder() {
+        let mut state = DiagnosticsState::new(0x12);
+        let mut decoder = FrameDecoder::<256>::new();
+        // Write single register with a corrupted CRC
+        decoder.push(&[0x12, 0x06, 0x22, 0x22, 0xAB, 0xCD, 0x9F, 0xBF]);
+        decoder.push(&[0x12, 0x06, 0x22, 0x22, 0xAB, 0xCD, 0x9F, 0xBE]);
+        loop {
+            match decoder.next_request() {
+                Ok(Some(adu)) => {
+                    state.record_request(&adu);
+                }
+                Ok(None) => break,
+                Err(RequestError::Frame(err)) => state.record_decode_error(&err),
+                Err(err) => panic!("Unexpected error: {}", err),
+            }
+        }
+        assert_eq!(counter(&mut state, d::ReturnBusMessageCount), 1);
+        assert_eq!(counter(&mut state, d::ReturnBusCommunicationErrorCount), 1);
+        let buf = &mut [0; 64];
+        assert_eq!(state.copy_events_to(buf), 2);
+        assert_eq!(buf[..2], [EV_RECEIVE, EV_RECEIVE | EV_RECEIVE_COMM_ERROR]);
+    }
+
+    #[test]
+    fn process_broadcast_write_without_response() {
+        let mut state = DiagnosticsState::new(0x12);
+        let req = request(0x00, Request::WriteSingleRegister(0x01, 0xABCD));
+        let disposition = state.record_request(&req);
+        assert!(disposition.process);
+        assert!(!disposition.respond);
+        assert_eq!(counter(&mut state, d::ReturnServerMessageCount), 1);
+        assert_eq!(counter(&mut state, d::ReturnServerNoResponseCount), 1);
+        let buf = &mut [0; 64];
+        assert_eq!(state.copy_events_to(buf), 1);
+        assert_eq!(buf[0], 0xC0);
+    }
+
+    #[test]
+    fn count_exceptions() {
+        let mut state = DiagnosticsState::new(0x12);
+        state.record_response(&response(Err(Exception::ServerDeviceBusy)));
+        state.record_response(&response(Err(Exception::IllegalDataAddress)));
+        state.record_response(&response(Ok(Response::WriteSingleCoil(0))));
+
+        assert_eq!(counter(&mut state, d::ReturnBusExceptionErrorCount), 2);
+        assert_eq!(counter(&mut state, d::ReturnServerBusyCount), 1);
+        assert_eq!(counter(&mut state, d::ReturnServerNakCount), 0);
+        assert_eq!(state.comm_event_counter(), 1);
+    }
+
+    #[test]
+    fn get_comm_event_counter() {
+        let mut state = DiagnosticsState::new(0x12);
+        state.record_response(&response(Ok(Response::WriteSingleCoil(0))));
+        state.record_response(&response(Ok(Response::GetCommEventCounter(0, 1))));
+        state.record_response(&response(Err(Exception::IllegalFunction)));
+        state.set_busy(true);
+        let buf = &mut [0; 2];
+        let rsp = state.reply(&Request::GetCommEventCounter, buf).unwrap();
+        assert_eq!(
+            rsp,
+            ResponsePdu(Ok(Response::GetCommEventCounter(0xFFFF, 1)))
+        );
+    }
+
+    #[test]
+    fn get_comm_event_log() {
+        let mut state = DiagnosticsState::new(0x12);
+        assert!(
+            state
+                .record_request(&request(0x12, Request::ReadCoils(0, 1)))
+                .respond
+        );
+        state.record_response(&response(Ok(Response::WriteSingleCoil(0))));
+        assert!(
+            !state
+                .record_request(&request(0x00, Request::ReadCoils(0, 1)))
+                .respond
+        );
+        state.record_decode_error(&Error::Crc(0x1234, 0x4321));
+        state.record_response(&response(Err(Exception::ServerDeviceBusy)));
+
+        let buf = &mut [0; 64];
+        let rsp = state.reply(&Request::GetCommEventLog, buf).unwrap();
+        assert_eq!(
+            rsp,
+            ResponsePdu(Ok(Response::GetCommEventLog(
+                0x0000,
+                1,
+                2,
+                &[0x44, 0x82, 0xC0, 0x40, 0x80]
+            )))
+        );
+    }
+
+    #[test]
+    fn comm_event_log_keeps_the_last_64_events() {
+        let mut state = DiagnosticsState::new(0x12);
+        for _ in 0..70 {
+            state.record_response(&response(Ok(Response::WriteSingleCoil(0))));
+        }
+        state.record_response(&response(Err(Exception::ServerDeviceFailure)));
+        let buf = &mut [0; 100];
+        assert_eq!(state.copy_events_to(buf), 64);
+        assert_eq!(buf[0], 0x42);
+        assert!(buf[1..64].iter().all(|e| *e == 0x40));
+    }
+
+    #[test]
+    fn listen_only_mode() {
+        let mut state = DiagnosticsState::new(0x12);
+        let req = request(0x12, diagnostics(d::ForceListenOnlyMode, &[0x00, 0x00]));
+        assert!(!state.record_request(&req).respond);
+        assert!(state.listen_only());
+        assert!(
+            !state
+                .record_request(&request(0x12, Request::ReadCoils(0, 1)))
+                .respond
+        );
+        assert_eq!(counter(&mut state, d::ReturnServerNoResponseCount), 2);
+
+        let req = request(
+            0x12,
+            diagnostics(d::RestartCommunicationsOption, &[0xFF, 0x00]),
+        );
+        assert!(!state.record_request(&req).respond);
+        assert!(!state.listen_only());
+        let buf = &mut [0; 64];
+        assert_eq!(state.copy_events_to(buf), 1);
+        assert_eq!(buf[0], 0x00);
+    }
+
+    #[test]
+    fn restart_communications() {
+        let mut state = DiagnosticsState::new(0x12);
+        assert!(
+            state
+                .record_request(&request(0x12, Request::ReadCoils(0, 1)))
+                .respond
+        );
+        let req = diagnostics(d::RestartCommunicationsOption, &[0x00, 0x00]);
+        let buf = &mut [0; 2];
+        assert_eq!(
+            state.reply(&req, buf),
+            Some(ResponsePdu(Ok(Response::Diagnostics(
+                d::RestartCommunicationsOption,
+                Data {
+                    quantity: 1,
+                    data: &[0x00, 0x00]
+                }
+            ))))
+        );
+        assert_eq!(counter(&mut state, d::ReturnBusMessageCount), 0);
+        let buf = &mut [0; 64];
+        assert_eq!(state.copy_events_to(buf), 2);
+        assert_eq!(&buf[..2], &[0x00, 0x80]);
+    }
+
+    #[test]
+    fn diagnostic_register_and_clear_counters() {
+        let mut state = DiagnosticsState::new(0x12);
+        state.set_diagnostic_register(0xABCD);
+        assert_eq!(counter(&mut state, d::ReturnDiagnosticRegister), 0xABCD);
+        assert!(
+            state
+                .record_request(&request(0x12, Request::ReadCoils(0, 1)))
+                .respond
+        );
+        let buf = &mut [0; 2];
+        let req = diagnostics(d::ClearCountersAndDiagnosticRegister, &[0x00, 0x00]);
+        assert!(state.reply(&req, buf).is_some());
+        assert_eq!(counter(&mut state, d::ReturnDiagnosticRegister), 0);
+        assert_eq!(counter(&mut state, d::ReturnBusMessageCount), 0);
+    }
+
+    #[test]
+    fn not_handled_requests() {
+        let mut state = DiagnosticsState::new(0x12);
+        let buf = &mut [0; 2];
+        assert!(state.reply(&Request::ReadCoils(0, 1), buf).is_none());
+        let req = Request::Diagnostics(
+            d::Custom(0x42),
+            Data {
+                quantity: 0,
+                data: &[],
+            },
+        );
+        assert!(state.reply(&req, buf).is_none());
+    }
+}
@@ -3,7 +3,14 @@
 use super::*;
 use byteorder::{BigEndian, ByteOrder};
 
+pub mod client;
+#[cfg(feature = "rtu")]
+mod diagnostics;
 pub mod server;
+#[cfg(feature = "tokio-codec")]
+pub mod tokio_codec;
+#[cfg(feature = "rtu")]
+pub use self::diagnostics::*;
 pub use crate::frame::rtu::*;
 
 // [MODBUS over Serial Line Specification and Implementation Guide V1.02](http://modbus.org/docs/Modbus_over_serial_line_V1_02.pdf), page 13
@@ -30,7 +37,7 @@ pub struct FrameLocation {
 pub fn decode(
     decoder_type: DecoderType,
     buf: &[u8],
-) -> Result<Option<(DecodedFrame, FrameLocation)>> {
+) -> Result<Option<(DecodedFrame<'_>, FrameLocation)>> {
     use DecoderType::*;
     let mut drop_cnt = 0;
 
@@ -89,13 +96,13 @@ pub fn decode(
 }
 
 /// Extract a PDU frame out of a buffer.
-pub fn extract_frame(buf: &[u8], pdu_len: usize) -> Result<Option<DecodedFrame>> {
+pub fn extract_frame(buf: &[u8], pdu_len: usize) -> Result<Option<DecodedFrame<'_>>> {
     let adu_len = 1 + pdu_len;
     if buf.len() >= adu_len + 2 {
         let (adu_buf, buf) = buf.split_at(adu_len);
         let (crc_buf, _) = buf.split_at(2);
         // Read trailing CRC and verify ADU
-        let expected_crc = BigEndian::read_u16(&crc_buf);
+        let expected_crc = BigEndian::read_u16(crc_buf);
         let actual_crc = crc16(adu_buf);
         if expected_crc != actual_crc {
             return Err(Error::Crc(expected_crc, actual_crc));
@@ -125,7 +132,7 @@ pub fn crc16(data: &[u8]) -> u16 {
             }
         }
     }
-    crc << 8 | crc >> 8
+    crc.rotate_left(8)
 }
 
 /// Extract the PDU length out of the ADU request buffer.
@@ -137,9 +144,18 @@ pub fn request_pdu_len(adu_buf: &[u8]) -> Result<Option<usize>> {
     let len = match fn_code {
         0x01..=0x06 => Some(5),
         0x07 | 0x0B | 0x0C | 0x11 => Some(1),
+        0x08 => Some(5),
         0x0F | 0x10 => {
-            if adu_buf.len() > 4 {
-                Some(6 + adu_buf[4] as usize)
+            if adu_buf.len() > 6 {
+                Some(6 + adu_buf[6] as usize)
+            } else {
+                // incomplete frame
+                None
+            }
+        }
+        0x14 | 0x15 => {
+            if adu_buf.len() > 2 {
+                Some(2 + adu_buf[2] as usize)
             } else {
                 // incomplete frame
                 None
@@ -147,6 +163,7 @@ pub fn request_pdu_len(adu_buf: &[u8]) -> Result<Option<usize>> {
         }
         0x16 => Some(7),
         0x18 => Some(3),
+        0x2B => mei_request_pdu_len(&adu_buf[1..])?,
         0x17 => {
             if adu_buf.len() > 10 {
                 Some(10 + adu_buf[10] as usize)
@@ -169,7 +186,7 @@ pub fn response_pdu_len(adu_buf: &[u8]) -> Result<Option<usize>> {
     }
     let fn_code = adu_buf[1];
     let len = match fn_code {
-        0x01..=0x04 | 0x0C | 0x17 => {
+        0x01..=0x04 | 0x0C | 0x11 | 0x14 | 0x15 | 0x17 => {
             if adu_buf.len() > 2 {
                 Some(2 + adu_buf[2] as usize)
             } else {
@@ -177,7 +194,7 @@ pub fn response_pdu_len(adu_buf: &[u8]) -> Result<Option<usize>> {
                 None
             }
         }
-        0x05 | 0x06 | 0x0B | 0x0F | 0x10 => Some(5),
+        0x05 | 0x06 | 0x08 | 0x0B | 0x0F | 0x10 => Some(5),
         0x07 => Some(2),
         0x16 => Some(7),
         0x18 => {
@@ -188,12 +205,55 @@ pub fn response_pdu_len(adu_buf: &[u8]) -> Result<Option<usize>> {
                 None
             }
         }
+        0x2B => mei_response_pdu_len(&adu_buf[1..])?,
         0x81..=0xAB => Some(2),
         _ => return Err(Error::FnCode(fn_code)),
     };
     Ok(len)
 }
 
+/// The RTU transport.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rtu;
+
+impl Transport for Rtu {
+    type Header = Header;
+    const INIT: Self = Self;
+    const MAX_FRAME_LEN: usize = MAX_FRAME_LEN;
+    const DROP_ON_ERR: usize = MAX_FRAME_LEN - 1;
+
+    fn locate(&mut self, decoder_type: DecoderType, buf: &[u8]) -> Result<Option<(usize, usize)>> {
+        // A complete frame at the start of the buffer is located even if
+        // its CRC is invalid, so that the error is reported by `extract`
+        // instead of being skipped while searching for the next frame.
+        let pdu_len = match decoder_type {
+            DecoderType::Request => request_pdu_len(buf),
+            DecoderType::Response => response_pdu_len(buf),
+        };
+        if let Ok(Some(pdu_len)) = pdu_len {
+            if buf.len() >= pdu_len + 3 {
+                return Ok(Some((0, pdu_len + 3)));
+            }
+        }
+        decode(decoder_type, buf).map(|frame| frame.map(|(_, loc)| (loc.start, loc.size)))
+    }
+    fn extract<'a>(&'a mut self, frame: &'a [u8]) -> Result<Option<(Header, &'a [u8])>> {
+        extract_frame(frame, frame.len() - 3)
+            .map(|frame| frame.map(|DecodedFrame { slave, pdu }| (Header { slave }, pdu)))
+    }
+    fn encode_request(adu: RequestAdu, buf: &mut [u8]) -> Result<usize> {
+        client::encode_request(adu, buf)
+    }
+    fn encode_response(adu: ResponseAdu, buf: &mut [u8]) -> Result<usize> {
+        server::encode_response(adu, buf)
+    }
+}
+
+/// An incremental decoder for RTU frames with a fixed capacity of `N` bytes.
+///
+/// `N` must be at least 256 bytes, the maximum size of an RTU frame.
+pub type FrameDecoder<const N: usize> = super::FrameDecoder<Rtu, N>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,7 +293,8 @@ mod tests {
         buf[1] = 0x07;
         assert_eq!(request_pdu_len(buf).unwrap(), Some(1));
 
-        // TODO: 0x08
+        buf[1] = 0x08;
+        assert_eq!(request_pdu_len(buf).unwrap(), Some(5));
 
         buf[1] = 0x0B;
         assert_eq!(request_pdu_len(buf).unwrap(), Some(1));
@@ -242,19 +303,23 @@ mod tests {
         assert_eq!(request_pdu_len(buf).unwrap(), Some(1));
 
         buf[1] = 0x0F;
-        buf[4] = 99;
+        buf[6] = 99; // byte count
         assert_eq!(request_pdu_len(buf).unwrap(), Some(105));
 
         buf[1] = 0x10;
-        buf[4] = 99;
+        buf[6] = 99; // byte count
         assert_eq!(request_pdu_len(buf).unwrap(), Some(105));
 
         buf[1] = 0x11;
         assert_eq!(request_pdu_len(buf).unwrap(), Some(1));
 
-        // TODO: 0x14
+        buf[1] = 0x14;
+        buf[2] = 99;
+        assert_eq!(request_pdu_len(buf).unwrap(), Some(101));
 
-        // TODO: 0x15
+        buf[1] = 0x15;
+        buf[2] = 99;
+        assert_eq!(request_pdu_len(buf).unwrap(), Some(101));
 
         buf[1] = 0x16;
         assert_eq!(request_pdu_len(buf).unwrap(), Some(7));
@@ -266,7 +331,12 @@ mod tests {
         buf[1] = 0x18;
         assert_eq!(request_pdu_len(buf).unwrap(), Some(3));
 
-        // TODO: 0x2B
+        buf[1] = 0x2B;
+        buf[2] = 0x0E;
+        assert_eq!(request_pdu_len(buf).unwrap(), Some(4));
+
+        buf[2] = 0x0D;
+        assert_eq!(request_pdu_len(buf).err().unwrap(), Error::MeiType(0x0D));
     }
 
     #[test]
@@ -301,7 +371,8 @@ mod tests {
         buf[1] = 0x07;
         assert_eq!(response_pdu_len(buf).unwrap(), Some(2));
 
-        // TODO: 0x08
+        buf[1] = 0x08;
+        assert_eq!(response_pdu_len(buf).unwrap(), Some(5));
 
         buf[1] = 0x0B;
         assert_eq!(response_pdu_len(buf).unwrap(), Some(5));
@@ -315,11 +386,14 @@ mod tests {
         buf[1] = 0x10;
         assert_eq!(response_pdu_len(buf).unwrap(), Some(5));
 
-        // TODO: 0x11
+        buf[1] = 0x11;
+        assert_eq!(response_pdu_len(buf).unwrap(), Some(101));
 
-        // TODO: 0x14
+        buf[1] = 0x14;
+        assert_eq!(response_pdu_len(buf).unwrap(), Some(101));
 
-        // TODO: 0x15
+        buf[1] = 0x15;
+        assert_eq!(response_pdu_len(buf).unwrap(), Some(101));
 
         buf[1] = 0x16;
         assert_eq!(response_pdu_len(buf).unwrap(), Some(7));
@@ -332,7 +406,9 @@ mod tests {
         buf[3] = 0x00; // byte count Lo
         assert_eq!(response_pdu_len(buf).unwrap(), Some(259));
 
-        // TODO: 0x2B
+        buf[1] = 0x2B;
+        buf[2] = 0x0E;
+        assert_eq!(response_pdu_len(buf).unwrap(), None);
 
         for i in 0x81..0xAB {
             buf[1] = i;
@@ -422,4 +498,91 @@ mod tests {
             assert!(decode(DecoderType::Response, buf).is_err());
         }
     }
+
+    mod frame_decoder_state {
+
+        use super::*;
+
+        const WRITE_SINGLE_REGISTER_REQ: &[u8] = &[0x12, 0x06, 0x22, 0x22, 0xAB, 0xCD, 0x9F, 0xBE];
+
+        #[test]
+        fn decode_requests_pushed_in_parts() {
+            let mut decoder = FrameDecoder::<256>::new();
+            assert!(decoder.is_empty());
+            assert_eq!(decoder.push(&WRITE_SINGLE_REGISTER_REQ[..3]), 3);
+            assert!(decoder.next_request().unwrap().is_none());
+            decoder.push(&WRITE_SINGLE_REGISTER_REQ[3..]);
+            decoder.push(WRITE_SINGLE_REGISTER_REQ);
+            let adu = decoder.next_request().unwrap().unwrap();
+            assert_eq!(adu.hdr.slave, 0x12);
+            assert_eq!(
+                adu.pdu,
+                RequestPdu(Request::WriteSingleRegister(0x2222, 0xABCD))
+            );
+            assert_eq!(decoder.len(), 8);
+            assert!(decoder.next_request().unwrap().is_some());
+            assert!(decoder.next_request().unwrap().is_none());
+            assert!(decoder.is_empty());
+            assert_eq!(decoder.dropped(), 0);
+        }
+
+        #[test]
+        fn drop_garbage_in_front_of_a_frame() {
+            let mut decoder = FrameDecoder::<256>::new();
+            decoder.push(&[0x00, 0x00]);
+            decoder.push(WRITE_SINGLE_REGISTER_REQ);
+            assert!(decoder.next_request().unwrap().is_some());
+            assert_eq!(decoder.dropped(), 2);
+            assert!(decoder.is_empty());
+        }
+
+        #[test]
+        fn report_frame_with_invalid_crc() {
+            let mut decoder = FrameDecoder::<256>::new();
+            let mut corrupted = [0; 8];
+            corrupted.copy_from_slice(WRITE_SINGLE_REGISTER_REQ);
+            corrupted[7] ^= 0x01;
+            decoder.push(&corrupted);
+            decoder.push(WRITE_SINGLE_REGISTER_REQ);
+            assert!(matches!(
+                decoder.next_request().err().unwrap(),
+                RequestError::Frame(Error::Crc(_, _))
+            ));
+            assert!(decoder.next_request().unwrap().is_some());
+            assert!(decoder.next_request().unwrap().is_none());
+            assert_eq!(decoder.dropped(), 8);
+        }
+
+        #[test]
+        fn decode_response() {
+            let mut decoder = FrameDecoder::<256>::new();
+            decoder.push(&[0x12, 0x83, 0x02, 0x31, 0x34]);
+            let adu = decoder.next_response().unwrap().unwrap();
+            assert_eq!(adu.hdr.slave, 0x12);
+            assert!(adu.pdu.0.is_err());
+        }
+
+        #[test]
+        fn reject_frame_that_does_not_fit_into_the_buffer() {
+            let mut decoder = FrameDecoder::<256>::new();
+            let mut buf = [0; 256];
+            // Write multiple registers with a byte count of 255
+            buf[..7].copy_from_slice(&[0x12, 0x10, 0x00, 0x00, 0x00, 0x7F, 0xFF]);
+            assert_eq!(decoder.push(&buf), 256);
+            assert_eq!(
+                decoder.next_request().err().unwrap(),
+                RequestError::Frame(Error::BufferSize)
+            );
+            assert_eq!(decoder.dropped(), MAX_FRAME_LEN - 1);
+        }
+
+        #[test]
+        fn clear_buffered_bytes() {
+            let mut decoder = FrameDecoder::<256>::new();
+            decoder.push(&WRITE_SINGLE_REGISTER_REQ[..4]);
+            decoder.clear();
+            decoder.push(WRITE_SINGLE_REGISTER_REQ);
+            assert!(decoder.next_request().unwrap().is_some());
+        }
+    }
 }
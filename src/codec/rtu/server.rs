@@ -1,32 +1,27 @@
 //! Modbus RTU server (slave) specific functions.
 use super::*;
 
+pub use crate::codec::dispatch;
+
 /// Decode an RTU request.
-pub fn decode_request(buf: &[u8]) -> Result<Option<RequestAdu>> {
-    decode(DecoderType::Request, buf)
-        .and_then(|frame| {
-            if let Some((DecodedFrame { slave, pdu }, _frame_pos)) = frame {
-                let hdr = Header { slave };
-                // Decoding of the PDU should are unlikely to fail due
-                // to transmission errors, because the frame's bytes
-                // have already been verified with the CRC.
-                Request::try_from(pdu)
-                    .map(RequestPdu)
-                    .map(|pdu| Some(RequestAdu { hdr, pdu }))
-                    .map_err(|err| {
-                        // Unrecoverable error
-                        error!("Failed to decode request PDU: {}", err);
-                        err
-                    })
-            } else {
-                Ok(None)
-            }
-        })
-        .or_else(|_| {
-            // Decoding the transport frame is non-destructive and must
-            // never fail!
-            unreachable!();
-        })
+///
+/// It returns `Ok(None)` if more bytes are needed.
+/// A [`RequestError::Frame`] means that no valid frame could be found
+/// and the buffered bytes should be discarded.
+/// A [`RequestError::Pdu`] carries the header of a valid frame
+/// with an invalid PDU that should be answered with an exception.
+pub fn decode_request(
+    buf: &[u8],
+) -> core::result::Result<Option<RequestAdu<'_>>, RequestError<Header>> {
+    let (DecodedFrame { slave, pdu }, _frame_pos) = match decode(DecoderType::Request, buf)? {
+        Some(frame) => frame,
+        None => return Ok(None),
+    };
+    let hdr = Header { slave };
+    // The frame's bytes have already been verified with the CRC,
+    // so an invalid PDU is not caused by transmission errors.
+    let pdu = decode_request_pdu(hdr, pdu)?;
+    Ok(Some(RequestAdu { hdr, pdu }))
 }
 
 /// Encode an RTU response.
@@ -55,6 +50,64 @@ mod tests {
         assert!(req.is_none());
     }
 
+    #[test]
+    fn decode_garbage() {
+        let buf = &[0xFF; MAX_FRAME_LEN + 8];
+        assert!(matches!(decode_request(buf), Err(RequestError::Frame(_))));
+    }
+
+    #[test]
+    fn decode_invalid_request_pdu() {
+        let buf = &mut [
+            0x12, // slave address
+            0x03, // function code
+            0x00, // addr
+            0x00, // addr
+            0x00, // quantity
+            0x7E, // quantity
+            0x00, // crc
+            0x00, // crc
+        ];
+        let crc = crc16(&buf[..6]);
+        BigEndian::write_u16(&mut buf[6..], crc);
+        let err = decode_request(buf).unwrap_err();
+        assert_eq!(
+            err,
+            RequestError::Pdu {
+                hdr: Header { slave: 0x12 },
+                fn_code: 0x03,
+                error: Error::Quantity(126),
+            }
+        );
+        let (hdr, ex) = err.exception_response().unwrap();
+        let rsp = ResponseAdu {
+            hdr,
+            pdu: ResponsePdu(Err(ex)),
+        };
+        let out = &mut [0; 5];
+        assert_eq!(encode_response(rsp, out).unwrap(), 5);
+        assert_eq!(out[..3], [0x12, 0x83, 0x03]);
+    }
+
+    #[test]
+    fn do_not_answer_invalid_broadcast_request_pdu() {
+        let buf = &mut [
+            0x00, // slave address
+            0x03, // function code
+            0x00, // addr
+            0x00, // addr
+            0x00, // quantity
+            0x7E, // quantity
+            0x00, // crc
+            0x00, // crc
+        ];
+        let crc = crc16(&buf[..6]);
+        BigEndian::write_u16(&mut buf[6..], crc);
+        let err = decode_request(buf).unwrap_err();
+        assert!(matches!(err, RequestError::Pdu { fn_code: 0x03, .. }));
+        assert_eq!(err.exception_response(), None);
+    }
+
     #[test]
     fn decode_partly_received_request() {
         let buf = &[
@@ -0,0 +1,74 @@
+//! [`tokio_util::codec`] implementations for Modbus RTU.
+use super::*;
+
+/// An RTU request ADU that owns its bytes.
+pub type OwnedRequestAdu = crate::codec::tokio_codec::OwnedRequestAdu<Header>;
+
+/// An RTU response ADU that owns its bytes.
+pub type OwnedResponseAdu = crate::codec::tokio_codec::OwnedResponseAdu<Header>;
+
+/// Client (master) codec that encodes requests and decodes responses.
+pub type ClientCodec = crate::codec::tokio_codec::ClientCodec<Rtu>;
+
+/// Server (slave) codec that decodes requests and encodes responses.
+pub type ServerCodec = crate::codec::tokio_codec::ServerCodec<Rtu>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use futures::{SinkExt, StreamExt};
+    use tokio_util::codec::{Decoder, Framed};
+
+    #[test]
+    fn decode_frames_and_advance_the_buffer() {
+        let mut codec = ServerCodec::default();
+        let mut buf = BytesMut::from(
+            &[
+                0x00, 0x12, 0x06, 0x22, 0x22, 0xAB, 0xCD, 0x9F, 0xBE, 0x12, 0x06, 0x22,
+            ][..],
+        );
+        let adu = codec.decode(&mut buf).unwrap().unwrap().unwrap();
+        assert_eq!(adu.hdr.slave, 0x12);
+        assert_eq!(
+            adu.adu().unwrap().pdu,
+            RequestPdu(Request::WriteSingleRegister(0x2222, 0xABCD))
+        );
+        assert_eq!(&buf[..], &[0x12, 0x06, 0x22]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert_eq!(buf.len(), 3);
+    }
+
+    #[test]
+    fn decode_exception_response() {
+        let mut codec = ClientCodec::default();
+        let mut buf = BytesMut::from(&[0x12, 0x83, 0x02, 0x31, 0x34][..]);
+        let adu = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(
+            adu.adu().unwrap().pdu,
+            ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::ReadHoldingRegisters,
+                exception: Exception::IllegalDataAddress,
+            }))
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn client_server_roundtrip() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut client = Framed::new(client, ClientCodec::default());
+        let mut server = Framed::new(server, ServerCodec::default());
+
+        let hdr = Header { slave: 0x12 };
+        let req = RequestPdu(Request::WriteSingleRegister(0x2222, 0xABCD));
+        client.send(RequestAdu { hdr, pdu: req }).await.unwrap();
+        let req_adu = server.next().await.unwrap().unwrap().unwrap();
+        assert_eq!(req_adu.adu().unwrap(), RequestAdu { hdr, pdu: req });
+
+        let rsp = ResponsePdu(Ok(Response::WriteSingleRegister(0x2222, 0xABCD)));
+        server.send(ResponseAdu { hdr, pdu: rsp }).await.unwrap();
+        let rsp_adu = client.next().await.unwrap().unwrap();
+        assert_eq!(rsp_adu.adu().unwrap(), ResponseAdu { hdr, pdu: rsp });
+    }
+}
@@ -0,0 +1,63 @@
+//! Modbus RTU over TCP client (master) specific functions.
+use super::*;
+
+pub use crate::codec::rtu::client::encode_request;
+
+/// Decode an RTU over TCP response.
+pub fn decode_response(buf: &[u8]) -> Result<Option<ResponseAdu<'_>>> {
+    decode(DecoderType::Response, buf).and_then(|frame| {
+        if let Some((DecodedFrame { slave, pdu }, _frame_pos)) = frame {
+            let hdr = Header { slave };
+            ResponsePdu::try_from(pdu).map(|pdu| Some(ResponseAdu { hdr, pdu }))
+        } else {
+            Ok(None)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_response_after_garbage() {
+        let buf = &[
+            0x00, // garbage
+            0x12, // slave address
+            0x83, // exception function code
+            0x02, // exception code
+            0x31, // crc
+            0x34, // crc
+        ];
+        let ResponseAdu { hdr, pdu } = decode_response(buf).unwrap().unwrap();
+        assert_eq!(hdr.slave, 0x12);
+        assert_eq!(
+            pdu,
+            ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::ReadHoldingRegisters,
+                exception: Exception::IllegalDataAddress,
+            }))
+        );
+    }
+
+    #[test]
+    fn encode_request_decode_response_roundtrip() {
+        let req_adu = RequestAdu {
+            hdr: Header { slave: 0x05 },
+            pdu: RequestPdu(Request::WriteSingleRegister(0x2222, 0xABCD)),
+        };
+        let buf = &mut [0; 100];
+        let len = encode_request(req_adu, buf).unwrap();
+        assert_eq!(
+            server::decode_request(&buf[..len]).unwrap().unwrap(),
+            req_adu
+        );
+
+        let rsp_adu = ResponseAdu {
+            hdr: Header { slave: 0x05 },
+            pdu: ResponsePdu(Ok(Response::WriteSingleRegister(0x2222, 0xABCD))),
+        };
+        let len = server::encode_response(rsp_adu, buf).unwrap();
+        assert_eq!(decode_response(&buf[..len]).unwrap().unwrap(), rsp_adu);
+    }
+}
@@ -0,0 +1,223 @@
+//! Modbus RTU over TCP
+//!
+//! Raw RTU frames (slave ID, PDU and CRC) that are tunneled through
+//! a stream transport like a TCP socket.
+//! There is no inter-character timing on a stream, so the decoder
+//! resynchronizes by searching for a frame with a valid CRC.
+
+use super::*;
+
+pub mod client;
+pub mod server;
+pub use super::rtu::{crc16, DecodedFrame, FrameLocation};
+pub use crate::frame::rtu::*;
+
+/// The maximum number of bytes that are searched for the start of a frame.
+pub const MAX_LOOK_AHEAD: usize = 256;
+
+/// Decode RTU PDU frames from a stream buffer.
+///
+/// If the bytes at the start of the buffer are not a valid frame,
+/// the bytes in front of the next frame with a valid CRC are skipped.
+/// Nothing is skipped as long as the frame at the start of the buffer
+/// is incomplete.
+/// If none of the first [`MAX_LOOK_AHEAD`] bytes can be the start
+/// of a frame, the error of the last attempt is returned and the caller
+/// should drop these bytes.
+pub fn decode(
+    decoder_type: DecoderType,
+    buf: &[u8],
+) -> Result<Option<(DecodedFrame<'_>, FrameLocation)>> {
+    use DecoderType::*;
+    let mut incomplete = false;
+    let mut last_err = None;
+
+    for start in 0..buf.len().min(MAX_LOOK_AHEAD) {
+        let raw_frame = &buf[start..];
+        let pdu_len = match decoder_type {
+            Request => rtu::request_pdu_len(raw_frame),
+            Response => rtu::response_pdu_len(raw_frame),
+        };
+        let res = pdu_len.and_then(|pdu_len| match pdu_len {
+            Some(pdu_len) => rtu::extract_frame(raw_frame, pdu_len).map(|frame| {
+                frame.map(|frame| {
+                    (
+                        frame,
+                        FrameLocation {
+                            start,
+                            size: pdu_len + 3,
+                        },
+                    )
+                })
+            }),
+            None => Ok(None),
+        });
+        match res {
+            Ok(Some((frame, location))) => {
+                if start > 0 {
+                    warn!(
+                        "Dropping {} byte(s) in front of the frame: {:X?}",
+                        start,
+                        &buf[..start]
+                    );
+                }
+                return Ok(Some((frame, location)));
+            }
+            // Wait for the rest of the frame instead of searching
+            // its payload for something that looks like a frame.
+            Ok(None) if start == 0 => return Ok(None),
+            // The frame might be completed by bytes that have not been
+            // received yet, but a following frame with a valid CRC
+            // can still be found.
+            Ok(None) => incomplete = true,
+            Err(err) => last_err = Some(err),
+        }
+    }
+    match last_err {
+        Some(err) if !incomplete => {
+            let pdu_type = match decoder_type {
+                Request => "request",
+                Response => "response",
+            };
+            error!(
+                "Failed to find a {} frame within {} byte(s): {}",
+                pdu_type, MAX_LOOK_AHEAD, err
+            );
+            Err(err)
+        }
+        _ => Ok(None),
+    }
+}
+
+/// The RTU over TCP transport.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RtuOverTcp;
+
+impl Transport for RtuOverTcp {
+    type Header = Header;
+    const INIT: Self = Self;
+    const MAX_FRAME_LEN: usize = rtu::Rtu::MAX_FRAME_LEN;
+    const DROP_ON_ERR: usize = MAX_LOOK_AHEAD;
+
+    fn locate(&mut self, decoder_type: DecoderType, buf: &[u8]) -> Result<Option<(usize, usize)>> {
+        decode(decoder_type, buf).map(|frame| frame.map(|(_, loc)| (loc.start, loc.size)))
+    }
+    fn extract<'a>(&'a mut self, frame: &'a [u8]) -> Result<Option<(Header, &'a [u8])>> {
+        rtu::extract_frame(frame, frame.len() - 3)
+            .map(|frame| frame.map(|DecodedFrame { slave, pdu }| (Header { slave }, pdu)))
+    }
+    fn encode_request(adu: RequestAdu, buf: &mut [u8]) -> Result<usize> {
+        client::encode_request(adu, buf)
+    }
+    fn encode_response(adu: ResponseAdu, buf: &mut [u8]) -> Result<usize> {
+        rtu::server::encode_response(adu, buf)
+    }
+}
+
+/// An incremental decoder for RTU over TCP frames with a fixed capacity of `N` bytes.
+///
+/// `N` must be at least 256 bytes, the maximum size of an RTU frame.
+pub type FrameDecoder<const N: usize> = super::FrameDecoder<RtuOverTcp, N>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const READ_HOLDING_REGISTERS_RSP: &[u8] = &[
+        0x01, // slave address
+        0x03, // function code
+        0x04, // byte count
+        0x89, //
+        0x02, //
+        0x42, //
+        0xC7, //
+        0x00, // crc
+        0x9D, // crc
+    ];
+
+    #[test]
+    fn decode_empty_buffer() {
+        assert!(decode(DecoderType::Response, &[]).unwrap().is_none());
+    }
+
+    #[test]
+    fn decode_complete_frame() {
+        let (frame, location) = decode(DecoderType::Response, READ_HOLDING_REGISTERS_RSP)
+            .unwrap()
+            .unwrap();
+        assert_eq!(frame.slave, 0x01);
+        assert_eq!(frame.pdu, &READ_HOLDING_REGISTERS_RSP[1..7]);
+        assert_eq!(location, FrameLocation { start: 0, size: 9 });
+    }
+
+    #[test]
+    fn decode_partly_received_frame() {
+        let res = decode(DecoderType::Response, &READ_HOLDING_REGISTERS_RSP[..8]).unwrap();
+        assert!(res.is_none());
+    }
+
+    #[test]
+    fn wait_for_incomplete_frame_instead_of_resync() {
+        let mut buf = [0; 12];
+        // The start of a read coils response with a byte count that
+        // covers the following (valid) frame.
+        buf[..3].copy_from_slice(&[0x01, 0x01, 0xFF]);
+        buf[3..].copy_from_slice(READ_HOLDING_REGISTERS_RSP);
+        assert!(decode(DecoderType::Response, &buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn resync_after_garbage_that_looks_like_the_start_of_a_frame() {
+        let mut buf = [0; 12];
+        // The start of a read coils response whose CRC does not match
+        // once the following frame has been received.
+        buf[..3].copy_from_slice(&[0x01, 0x01, 0x02]);
+        buf[3..].copy_from_slice(READ_HOLDING_REGISTERS_RSP);
+        let (frame, location) = decode(DecoderType::Response, &buf).unwrap().unwrap();
+        assert_eq!(frame.slave, 0x01);
+        assert_eq!(location, FrameLocation { start: 3, size: 9 });
+    }
+
+    #[test]
+    fn resync_after_corrupted_frame() {
+        let mut buf = [0; 18];
+        buf[..9].copy_from_slice(READ_HOLDING_REGISTERS_RSP);
+        buf[4] = 0x00;
+        buf[9..].copy_from_slice(READ_HOLDING_REGISTERS_RSP);
+        let (_, location) = decode(DecoderType::Response, &buf).unwrap().unwrap();
+        assert_eq!(location, FrameLocation { start: 9, size: 9 });
+    }
+
+    #[test]
+    fn give_up_after_look_ahead() {
+        let buf = [0xFF; MAX_LOOK_AHEAD + 1];
+        assert_eq!(
+            decode(DecoderType::Response, &buf).err().unwrap(),
+            Error::FnCode(0xFF)
+        );
+        let buf = [0xFF; MAX_LOOK_AHEAD];
+        assert!(decode(DecoderType::Response, &buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn decode_frames_pushed_into_frame_decoder() {
+        let mut decoder = FrameDecoder::<256>::new();
+        decoder.push(&[0x01, 0x01, 0x02]);
+        decoder.push(&READ_HOLDING_REGISTERS_RSP[..5]);
+        assert!(decoder.next_response().unwrap().is_none());
+        decoder.push(&READ_HOLDING_REGISTERS_RSP[5..]);
+        let adu = decoder.next_response().unwrap().unwrap();
+        assert_eq!(adu.hdr.slave, 0x01);
+        assert_eq!(decoder.dropped(), 3);
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn frame_decoder_drops_look_ahead_on_error() {
+        let mut decoder = FrameDecoder::<512>::new();
+        decoder.push(&[0xFF; MAX_LOOK_AHEAD + 1]);
+        assert!(decoder.next_response().is_err());
+        assert_eq!(decoder.dropped(), MAX_LOOK_AHEAD);
+        assert_eq!(decoder.len(), 1);
+    }
+}
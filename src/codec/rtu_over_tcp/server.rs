@@ -0,0 +1,54 @@
+//! Modbus RTU over TCP server (slave) specific functions.
+use super::*;
+
+pub use crate::codec::{dispatch, rtu::server::encode_response};
+
+/// Decode an RTU over TCP request.
+pub fn decode_request(
+    buf: &[u8],
+) -> core::result::Result<Option<RequestAdu<'_>>, RequestError<Header>> {
+    let (DecodedFrame { slave, pdu }, _frame_pos) = match decode(DecoderType::Request, buf)? {
+        Some(frame) => frame,
+        None => return Ok(None),
+    };
+    let hdr = Header { slave };
+    let pdu = decode_request_pdu(hdr, pdu)?;
+    Ok(Some(RequestAdu { hdr, pdu }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_write_single_register_request_after_garbage() {
+        let buf = &[
+            0x12, // garbage
+            0x06, // garbage
+            0x12, // slave address
+            0x06, // function code
+            0x22, // addr
+            0x22, // addr
+            0xAB, // value
+            0xCD, // value
+            0x9F, // crc
+            0xBE, // crc
+        ];
+        let RequestAdu { hdr, pdu } = decode_request(buf).unwrap().unwrap();
+        assert_eq!(hdr.slave, 0x12);
+        assert_eq!(
+            pdu,
+            RequestPdu(Request::WriteSingleRegister(0x2222, 0xABCD))
+        );
+    }
+
+    #[test]
+    fn decode_partly_received_request() {
+        let buf = &[
+            0x12, // slave address
+            0x06, // function code
+            0x22, // addr
+        ];
+        assert!(decode_request(buf).unwrap().is_none());
+    }
+}
@@ -0,0 +1,449 @@
+//! Server (slave) side request handling.
+use super::*;
+use core::result::Result;
+
+/// A Modbus server (slave) implementation.
+///
+/// Every function code has its own callback.
+/// Responses that contain data are written into the `target` buffer,
+/// e.g. with [`CoilsMut`] or [`DataMut`], and encoded from there.
+/// All callbacks respond with [`Exception::IllegalFunction`] by default.
+#[allow(unused_variables)]
+pub trait Service {
+    /// Read multiple coils (0x01)
+    fn read_coils<'b>(
+        &mut self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Coils<'b>, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Read multiple discrete inputs (0x02)
+    fn read_discrete_inputs<'b>(
+        &mut self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Coils<'b>, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Write a single coil (0x05)
+    fn write_single_coil(&mut self, address: Address, coil: Coil) -> Result<(), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Write multiple coils (0x0F)
+    fn write_multiple_coils(&mut self, address: Address, coils: Coils) -> Result<(), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Read multiple input registers (0x04)
+    fn read_input_registers<'b>(
+        &mut self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Data<'b>, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Read multiple holding registers (0x03)
+    fn read_holding_registers<'b>(
+        &mut self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Data<'b>, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Write a single holding register (0x06)
+    fn write_single_register(&mut self, address: Address, word: Word) -> Result<(), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Write multiple holding registers (0x10)
+    fn write_multiple_registers(&mut self, address: Address, data: Data) -> Result<(), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Write and read multiple holding registers (0x17)
+    ///
+    /// The write operation has to be performed before the read operation.
+    fn read_write_multiple_registers<'b>(
+        &mut self,
+        read_address: Address,
+        quantity: Quantity,
+        write_address: Address,
+        data: Data,
+        target: &'b mut [u8],
+    ) -> Result<Data<'b>, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Modify a holding register with an AND and an OR mask (0x16)
+    fn mask_write_register(
+        &mut self,
+        address: Address,
+        and_mask: AndMask,
+        or_mask: OrMask,
+    ) -> Result<(), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Read the content of a FIFO queue (0x18)
+    fn read_fifo_queue<'b>(
+        &mut self,
+        address: Address,
+        target: &'b mut [u8],
+    ) -> Result<Data<'b>, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Read file records (0x14)
+    fn read_file_record<'b>(
+        &mut self,
+        sub_requests: FileSubRequests,
+        target: &'b mut [u8],
+    ) -> Result<FileSubResponses<'b>, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Write file records (0x15)
+    fn write_file_record(&mut self, records: FileRecords) -> Result<(), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Read the device identification (0x2B / 0x0E)
+    fn read_device_identification<'b>(
+        &mut self,
+        code: ReadDeviceIdCode,
+        object_id: ObjectId,
+        target: &'b mut [u8],
+    ) -> Result<DeviceIdentification<'b>, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Read the exception status (0x07)
+    #[cfg(feature = "rtu")]
+    fn read_exception_status(&mut self) -> Result<u8, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Run a diagnostics sub-function (0x08)
+    #[cfg(feature = "rtu")]
+    fn diagnostics<'b>(
+        &mut self,
+        sub_fn: DiagnosticSubFunction,
+        data: Data,
+        target: &'b mut [u8],
+    ) -> Result<Data<'b>, Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Get the status and the comm event counter (0x0B)
+    #[cfg(feature = "rtu")]
+    fn get_comm_event_counter(&mut self) -> Result<(u16, u16), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Get the status, the event and message counters and the events (0x0C)
+    #[cfg(feature = "rtu")]
+    fn get_comm_event_log<'b>(
+        &mut self,
+        target: &'b mut [u8],
+    ) -> Result<(u16, u16, u16, &'b [u8]), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Get the server ID and the run indicator status (0x11)
+    #[cfg(feature = "rtu")]
+    fn report_server_id<'b>(
+        &mut self,
+        target: &'b mut [u8],
+    ) -> Result<(&'b [u8], bool), Exception> {
+        Err(Exception::IllegalFunction)
+    }
+    /// Handle a custom function code
+    fn custom<'b>(
+        &mut self,
+        fn_code: FnCode,
+        data: &[u8],
+        target: &'b mut [u8],
+    ) -> Result<&'b [u8], Exception> {
+        Err(Exception::IllegalFunction)
+    }
+}
+
+/// Process a request with a service and build the response ADU.
+///
+/// Responses that contain data are written into `buf`.
+/// Broadcast requests are processed as well, but `None` is returned
+/// because they must not be answered.
+pub fn dispatch<'a, H, S>(
+    hdr: H,
+    pdu: RequestPdu<'a>,
+    service: &mut S,
+    buf: &'a mut [u8],
+) -> Option<H::ResponseAdu<'a>>
+where
+    H: AduHeader,
+    S: Service + ?Sized,
+{
+    let pdu = dispatch_pdu(pdu, service, buf);
+    if hdr.is_broadcast() {
+        return None;
+    }
+    Some(hdr.response_adu(pdu))
+}
+
+/// Process a request PDU with a service and build the response PDU.
+pub(crate) fn dispatch_pdu<'a, S>(
+    pdu: RequestPdu<'a>,
+    service: &mut S,
+    buf: &'a mut [u8],
+) -> ResponsePdu<'a>
+where
+    S: Service + ?Sized,
+{
+    use crate::frame::Request as r;
+
+    let RequestPdu(req) = pdu;
+    let rsp = match req {
+        r::ReadCoils(address, quantity) => service
+            .read_coils(address, quantity, buf)
+            .map(Response::ReadCoils),
+        r::ReadDiscreteInputs(address, quantity) => service
+            .read_discrete_inputs(address, quantity, buf)
+            .map(Response::ReadDiscreteInputs),
+        r::WriteSingleCoil(address, coil) => service
+            .write_single_coil(address, coil)
+            .map(|_| Response::WriteSingleCoil(address)),
+        r::WriteMultipleCoils(address, coils) => service
+            .write_multiple_coils(address, coils)
+            .map(|_| Response::WriteMultipleCoils(address, coils.len() as Quantity)),
+        r::ReadInputRegisters(address, quantity) => service
+            .read_input_registers(address, quantity, buf)
+            .map(Response::ReadInputRegisters),
+        r::ReadHoldingRegisters(address, quantity) => service
+            .read_holding_registers(address, quantity, buf)
+            .map(Response::ReadHoldingRegisters),
+        r::WriteSingleRegister(address, word) => service
+            .write_single_register(address, word)
+            .map(|_| Response::WriteSingleRegister(address, word)),
+        r::WriteMultipleRegisters(address, data) => service
+            .write_multiple_registers(address, data)
+            .map(|_| Response::WriteMultipleRegisters(address, data.len() as Quantity)),
+        r::ReadWriteMultipleRegisters(read_address, quantity, write_address, data) => service
+            .read_write_multiple_registers(read_address, quantity, write_address, data, buf)
+            .map(Response::ReadWriteMultipleRegisters),
+        r::MaskWriteRegister(address, and_mask, or_mask) => service
+            .mask_write_register(address, and_mask, or_mask)
+            .map(|_| Response::MaskWriteRegister(address, and_mask, or_mask)),
+        r::ReadFifoQueue(address) => service
+            .read_fifo_queue(address, buf)
+            .map(Response::ReadFifoQueue),
+        r::ReadFileRecord(sub_requests) => service
+            .read_file_record(sub_requests, buf)
+            .map(Response::ReadFileRecord),
+        r::WriteFileRecord(records) => service
+            .write_file_record(records)
+            .map(|_| Response::WriteFileRecord(records)),
+        r::ReadDeviceIdentification(code, object_id) => service
+            .read_device_identification(code, object_id, buf)
+            .map(Response::ReadDeviceIdentification),
+        #[cfg(feature = "rtu")]
+        r::ReadExceptionStatus => service
+            .read_exception_status()
+            .map(Response::ReadExceptionStatus),
+        #[cfg(feature = "rtu")]
+        r::Diagnostics(sub_fn, data) => service
+            .diagnostics(sub_fn, data, buf)
+            .map(|data| Response::Diagnostics(sub_fn, data)),
+        #[cfg(feature = "rtu")]
+        r::GetCommEventCounter => service
+            .get_comm_event_counter()
+            .map(|(status, cnt)| Response::GetCommEventCounter(status, cnt)),
+        #[cfg(feature = "rtu")]
+        r::GetCommEventLog => {
+            service
+                .get_comm_event_log(buf)
+                .map(|(status, event_cnt, message_cnt, events)| {
+                    Response::GetCommEventLog(status, event_cnt, message_cnt, events)
+                })
+        }
+        #[cfg(feature = "rtu")]
+        r::ReportServerId => service
+            .report_server_id(buf)
+            .map(|(id, run)| Response::ReportServerId(id, run)),
+        r::Custom(fn_code, data) => service
+            .custom(fn_code, data, buf)
+            .map(|data| Response::Custom(fn_code, data)),
+    };
+    ResponsePdu(rsp.map_err(|exception| ExceptionResponse {
+        function: FnCode::from(req),
+        exception,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Registers([Word; 4]);
+
+    impl Service for Registers {
+        fn read_holding_registers<'b>(
+            &mut self,
+            address: Address,
+            quantity: Quantity,
+            target: &'b mut [u8],
+        ) -> Result<Data<'b>, Exception> {
+            let start = address as usize;
+            let end = start + quantity as usize;
+            let words = self
+                .0
+                .get(start..end)
+                .ok_or(Exception::IllegalDataAddress)?;
+            Data::from_words(words, target).map_err(|_| Exception::ServerDeviceFailure)
+        }
+        fn write_single_register(&mut self, address: Address, word: Word) -> Result<(), Exception> {
+            let reg = self
+                .0
+                .get_mut(address as usize)
+                .ok_or(Exception::IllegalDataAddress)?;
+            *reg = word;
+            Ok(())
+        }
+    }
+
+    /// Builds the read responses directly in the output buffer.
+    struct Generator;
+
+    impl Service for Generator {
+        fn read_discrete_inputs<'b>(
+            &mut self,
+            _: Address,
+            quantity: Quantity,
+            target: &'b mut [u8],
+        ) -> Result<Coils<'b>, Exception> {
+            let mut inputs = CoilsMut::new(target, quantity.into())
+                .map_err(|_| Exception::ServerDeviceFailure)?;
+            inputs.fill(true);
+            Ok(inputs.into_coils())
+        }
+        fn read_input_registers<'b>(
+            &mut self,
+            address: Address,
+            quantity: Quantity,
+            target: &'b mut [u8],
+        ) -> Result<Data<'b>, Exception> {
+            let mut data = DataMut::new(target, quantity.into())
+                .map_err(|_| Exception::ServerDeviceFailure)?;
+            for i in 0..quantity {
+                data.set(i.into(), address + i)
+                    .map_err(|_| Exception::ServerDeviceFailure)?;
+            }
+            Ok(data.into_data())
+        }
+    }
+
+    #[test]
+    fn dispatch_read_request() {
+        let mut service = Registers([1, 2, 3, 4]);
+        let buf = &mut [0; 8];
+        let req = RequestPdu(Request::ReadHoldingRegisters(1, 2));
+        let ResponsePdu(rsp) = dispatch_pdu(req, &mut service, buf);
+        match rsp.unwrap() {
+            Response::ReadHoldingRegisters(data) => {
+                assert_eq!(data.len(), 2);
+                assert_eq!(data.get(0), Some(2));
+                assert_eq!(data.get(1), Some(3));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn dispatch_write_request() {
+        let mut service = Registers([1, 2, 3, 4]);
+        let buf = &mut [0; 8];
+        let req = RequestPdu(Request::WriteSingleRegister(3, 0xABCD));
+        let rsp = dispatch_pdu(req, &mut service, buf);
+        assert_eq!(
+            rsp,
+            ResponsePdu(Ok(Response::WriteSingleRegister(3, 0xABCD)))
+        );
+        assert_eq!(service.0, [1, 2, 3, 0xABCD]);
+    }
+
+    #[test]
+    fn dispatch_request_that_fails() {
+        let mut service = Registers([1, 2, 3, 4]);
+        let buf = &mut [0; 8];
+        let req = RequestPdu(Request::ReadHoldingRegisters(3, 2));
+        let rsp = dispatch_pdu(req, &mut service, buf);
+        assert_eq!(
+            rsp,
+            ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::ReadHoldingRegisters,
+                exception: Exception::IllegalDataAddress,
+            }))
+        );
+    }
+
+    #[test]
+    fn dispatch_request_adu() {
+        let mut service = Registers([1, 2, 3, 4]);
+        let buf = &mut [0; 8];
+        let hdr = rtu::Header { slave: 0x12 };
+        let req = RequestPdu(Request::WriteSingleRegister(0, 0xABCD));
+        let rsp = dispatch(hdr, req, &mut service, buf).unwrap();
+        assert_eq!(rsp.hdr, hdr);
+        assert_eq!(
+            rsp.pdu,
+            ResponsePdu(Ok(Response::WriteSingleRegister(0, 0xABCD)))
+        );
+
+        // A broadcast is processed without a response
+        let hdr = rtu::Header { slave: 0 };
+        let req = RequestPdu(Request::WriteSingleRegister(1, 0x1234));
+        assert!(dispatch(hdr, req, &mut service, buf).is_none());
+        assert_eq!(service.0, [0xABCD, 0x1234, 3, 4]);
+
+        let hdr = tcp::Header {
+            transaction_id: 42,
+            unit_id: 0,
+        };
+        let req = RequestPdu(Request::ReadHoldingRegisters(0, 1));
+        let rsp = dispatch(hdr, req, &mut service, buf).unwrap();
+        assert_eq!(rsp.hdr, hdr);
+    }
+
+    #[test]
+    fn encode_responses_built_in_the_output_buffer() {
+        let mut service = Generator;
+        let hdr = rtu::Header { slave: 0x12 };
+        let buf = &mut [0; 8];
+        let out = &mut [0; 16];
+
+        let req = RequestPdu(Request::ReadInputRegisters(0x10, 2));
+        let rsp = dispatch(hdr, req, &mut service, buf).unwrap();
+        let len = rtu::server::encode_response(rsp, out).unwrap();
+        assert_eq!(out[..len - 2], [0x12, 0x04, 0x04, 0x00, 0x10, 0x00, 0x11]);
+
+        let req = RequestPdu(Request::ReadDiscreteInputs(0, 10));
+        let rsp = dispatch(hdr, req, &mut service, buf).unwrap();
+        let len = rtu::server::encode_response(rsp, out).unwrap();
+        assert_eq!(out[..len - 2], [0x12, 0x02, 0x02, 0xFF, 0x03]);
+    }
+
+    #[test]
+    fn dispatch_unsupported_request() {
+        let mut service = Registers([1, 2, 3, 4]);
+        let buf = &mut [0; 8];
+        let req = RequestPdu(Request::ReadCoils(0, 8));
+        let rsp = dispatch_pdu(req, &mut service, buf);
+        assert_eq!(
+            rsp,
+            ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::ReadCoils,
+                exception: Exception::IllegalFunction,
+            }))
+        );
+        let req = RequestPdu(Request::Custom(FnCode::Custom(0x42), &[0x01]));
+        let rsp = dispatch_pdu(req, &mut service, buf);
+        assert_eq!(
+            rsp,
+            ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::Custom(0x42),
+                exception: Exception::IllegalFunction,
+            }))
+        );
+    }
+}
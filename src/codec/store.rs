@@ -0,0 +1,452 @@
+//! In-memory data store for simulated servers (slaves).
+use super::*;
+use core::{ops::Range, result::Result};
+
+/// The data tables of a Modbus server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Table {
+    Coils,
+    DiscreteInputs,
+    InputRegisters,
+    HoldingRegisters,
+}
+
+impl Table {
+    const fn idx(self) -> usize {
+        self as usize
+    }
+}
+
+/// A [`Service`] that keeps its data in four fixed-size tables.
+///
+/// The tables hold `C` coils, `D` discrete inputs, `I` input registers
+/// and `H` holding registers. Each table starts at its base address
+/// (`0` by default). Accesses outside of a table fail with
+/// [`Exception::IllegalDataAddress`].
+///
+/// Ranges of coils and holding registers that were written by requests
+/// are recorded and can be polled with [`DataStore::take_changes`].
+/// Discrete inputs and input registers are read-only for clients,
+/// so no changes are ever recorded for them.
+#[derive(Debug, Clone)]
+pub struct DataStore<const C: usize, const D: usize, const I: usize, const H: usize> {
+    coils: [Coil; C],
+    discrete_inputs: [Coil; D],
+    input_registers: [Word; I],
+    holding_registers: [Word; H],
+    base_addresses: [Address; 4],
+    changes: [Option<Range<usize>>; 4],
+}
+
+impl<const C: usize, const D: usize, const I: usize, const H: usize> Default
+    for DataStore<C, D, I, H>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const C: usize, const D: usize, const I: usize, const H: usize> DataStore<C, D, I, H> {
+    /// Create a store with all tables cleared.
+    pub const fn new() -> Self {
+        Self {
+            coils: [false; C],
+            discrete_inputs: [false; D],
+            input_registers: [0; I],
+            holding_registers: [0; H],
+            base_addresses: [0; 4],
+            changes: [None, None, None, None],
+        }
+    }
+    /// Address of the first item of a table.
+    pub const fn base_address(&self, table: Table) -> Address {
+        self.base_addresses[table.idx()]
+    }
+    /// Move a table to a different base address.
+    pub fn set_base_address(&mut self, table: Table, address: Address) {
+        self.base_addresses[table.idx()] = address;
+    }
+    /// Pack coils into `target`.
+    pub fn coils<'b>(
+        &self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Coils<'b>, Exception> {
+        let range = self.range(Table::Coils, address, quantity.into())?;
+        pack(&self.coils[range], target)
+    }
+    /// Overwrite coils without recording a change.
+    pub fn set_coils(&mut self, address: Address, coils: Coils) -> Result<(), Exception> {
+        let range = self.range(Table::Coils, address, coils.len())?;
+        unpack(coils, &mut self.coils[range]);
+        Ok(())
+    }
+    /// Pack discrete inputs into `target`.
+    pub fn discrete_inputs<'b>(
+        &self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Coils<'b>, Exception> {
+        let range = self.range(Table::DiscreteInputs, address, quantity.into())?;
+        pack(&self.discrete_inputs[range], target)
+    }
+    /// Overwrite discrete inputs.
+    ///
+    /// Like all local writes this does not record a change.
+    pub fn set_discrete_inputs(
+        &mut self,
+        address: Address,
+        inputs: Coils,
+    ) -> Result<(), Exception> {
+        let range = self.range(Table::DiscreteInputs, address, inputs.len())?;
+        unpack(inputs, &mut self.discrete_inputs[range]);
+        Ok(())
+    }
+    /// Copy input registers into `target`.
+    pub fn input_registers<'b>(
+        &self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Data<'b>, Exception> {
+        let range = self.range(Table::InputRegisters, address, quantity.into())?;
+        words(&self.input_registers[range], target)
+    }
+    /// Overwrite input registers.
+    ///
+    /// Like all local writes this does not record a change.
+    pub fn set_input_registers(&mut self, address: Address, data: Data) -> Result<(), Exception> {
+        let range = self.range(Table::InputRegisters, address, data.len())?;
+        copy_words(data, &mut self.input_registers[range]);
+        Ok(())
+    }
+    /// Copy holding registers into `target`.
+    pub fn holding_registers<'b>(
+        &self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Data<'b>, Exception> {
+        let range = self.range(Table::HoldingRegisters, address, quantity.into())?;
+        words(&self.holding_registers[range], target)
+    }
+    /// Overwrite holding registers without recording a change.
+    pub fn set_holding_registers(&mut self, address: Address, data: Data) -> Result<(), Exception> {
+        let range = self.range(Table::HoldingRegisters, address, data.len())?;
+        copy_words(data, &mut self.holding_registers[range]);
+        Ok(())
+    }
+    /// Take the address and quantity of the items that were written
+    /// by requests since the last call.
+    ///
+    /// Multiple writes are merged into a single range that covers all of them.
+    /// This is an over-approximation: if the writes are not adjacent, the
+    /// range also contains the untouched items in between.
+    /// Only coils and holding registers can be written by requests,
+    /// so this always returns `None` for the other tables.
+    pub fn take_changes(&mut self, table: Table) -> Option<(Address, Quantity)> {
+        let range = self.changes[table.idx()].take()?;
+        let address = self.base_address(table) + range.start as Address;
+        Some((address, range.len() as Quantity))
+    }
+
+    fn range(
+        &self,
+        table: Table,
+        address: Address,
+        quantity: usize,
+    ) -> Result<Range<usize>, Exception> {
+        let len = match table {
+            Table::Coils => C,
+            Table::DiscreteInputs => D,
+            Table::InputRegisters => I,
+            Table::HoldingRegisters => H,
+        };
+        let start = address
+            .checked_sub(self.base_address(table))
+            .ok_or(Exception::IllegalDataAddress)? as usize;
+        let end = start + quantity;
+        if end > len {
+            return Err(Exception::IllegalDataAddress);
+        }
+        Ok(start..end)
+    }
+
+    fn record_change(&mut self, table: Table, range: Range<usize>) {
+        if range.is_empty() {
+            return;
+        }
+        let change = &mut self.changes[table.idx()];
+        *change = match change.take() {
+            Some(prev) => Some(prev.start.min(range.start)..prev.end.max(range.end)),
+            None => Some(range),
+        };
+    }
+}
+
+fn pack<'b>(coils: &[Coil], target: &'b mut [u8]) -> Result<Coils<'b>, Exception> {
+    let mut packed =
+        CoilsMut::new(target, coils.len()).map_err(|_| Exception::ServerDeviceFailure)?;
+    packed
+        .set_range(0, coils)
+        .map_err(|_| Exception::ServerDeviceFailure)?;
+    Ok(packed.into_coils())
+}
+
+fn unpack(coils: Coils, target: &mut [Coil]) {
+    for (t, c) in target.iter_mut().zip(coils) {
+        *t = c;
+    }
+}
+
+fn words<'b>(words: &[Word], target: &'b mut [u8]) -> Result<Data<'b>, Exception> {
+    let mut data = DataMut::new(target, words.len()).map_err(|_| Exception::ServerDeviceFailure)?;
+    data.set_range(0, words)
+        .map_err(|_| Exception::ServerDeviceFailure)?;
+    Ok(data.into_data())
+}
+
+fn copy_words(data: Data, target: &mut [Word]) {
+    for (t, w) in target.iter_mut().zip(data) {
+        *t = w;
+    }
+}
+
+impl<const C: usize, const D: usize, const I: usize, const H: usize> Service
+    for DataStore<C, D, I, H>
+{
+    fn read_coils<'b>(
+        &mut self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Coils<'b>, Exception> {
+        self.coils(address, quantity, target)
+    }
+    fn read_discrete_inputs<'b>(
+        &mut self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Coils<'b>, Exception> {
+        self.discrete_inputs(address, quantity, target)
+    }
+    fn write_single_coil(&mut self, address: Address, coil: Coil) -> Result<(), Exception> {
+        let range = self.range(Table::Coils, address, 1)?;
+        self.coils[range.start] = coil;
+        self.record_change(Table::Coils, range);
+        Ok(())
+    }
+    fn write_multiple_coils(&mut self, address: Address, coils: Coils) -> Result<(), Exception> {
+        let range = self.range(Table::Coils, address, coils.len())?;
+        unpack(coils, &mut self.coils[range.clone()]);
+        self.record_change(Table::Coils, range);
+        Ok(())
+    }
+    fn read_input_registers<'b>(
+        &mut self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Data<'b>, Exception> {
+        self.input_registers(address, quantity, target)
+    }
+    fn read_holding_registers<'b>(
+        &mut self,
+        address: Address,
+        quantity: Quantity,
+        target: &'b mut [u8],
+    ) -> Result<Data<'b>, Exception> {
+        self.holding_registers(address, quantity, target)
+    }
+    fn write_single_register(&mut self, address: Address, word: Word) -> Result<(), Exception> {
+        let range = self.range(Table::HoldingRegisters, address, 1)?;
+        self.holding_registers[range.start] = word;
+        self.record_change(Table::HoldingRegisters, range);
+        Ok(())
+    }
+    fn write_multiple_registers(&mut self, address: Address, data: Data) -> Result<(), Exception> {
+        let range = self.range(Table::HoldingRegisters, address, data.len())?;
+        copy_words(data, &mut self.holding_registers[range.clone()]);
+        self.record_change(Table::HoldingRegisters, range);
+        Ok(())
+    }
+    fn read_write_multiple_registers<'b>(
+        &mut self,
+        read_address: Address,
+        quantity: Quantity,
+        write_address: Address,
+        data: Data,
+        target: &'b mut [u8],
+    ) -> Result<Data<'b>, Exception> {
+        // Check the read range before anything gets written.
+        self.range(Table::HoldingRegisters, read_address, quantity.into())?;
+        self.write_multiple_registers(write_address, data)?;
+        self.holding_registers(read_address, quantity, target)
+    }
+    fn mask_write_register(
+        &mut self,
+        address: Address,
+        and_mask: AndMask,
+        or_mask: OrMask,
+    ) -> Result<(), Exception> {
+        let range = self.range(Table::HoldingRegisters, address, 1)?;
+        let reg = &mut self.holding_registers[range.start];
+        *reg = mask_register(*reg, and_mask, or_mask);
+        self.record_change(Table::HoldingRegisters, range);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Store = DataStore<16, 8, 4, 4>;
+
+    #[test]
+    fn read_and_write_registers() {
+        let mut store = Store::new();
+        let buf = &mut [0; 8];
+        store.write_single_register(2, 0xABCD).unwrap();
+        let data = store.read_holding_registers(1, 3, buf).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data.get(0), Some(0));
+        assert_eq!(data.get(1), Some(0xABCD));
+        assert_eq!(data.get(2), Some(0));
+
+        store.mask_write_register(2, 0x00FF, 0x1200).unwrap();
+        let data = store.read_holding_registers(2, 1, buf).unwrap();
+        assert_eq!(data.get(0), Some(0x12CD));
+    }
+
+    #[test]
+    fn read_and_write_coils() {
+        let mut store = Store::new();
+        let buf = &mut [0; 2];
+        let coils = Coils::from_bools(&[true, false, true], buf).unwrap();
+        store.write_multiple_coils(13, coils).unwrap();
+        store.write_single_coil(0, true).unwrap();
+        let buf = &mut [0; 2];
+        let coils = store.read_coils(0, 16, buf).unwrap();
+        assert_eq!(coils.len(), 16);
+        assert_eq!(buf[..], [0b0000_0001, 0b1010_0000]);
+    }
+
+    #[test]
+    fn bulk_access_of_read_only_tables() {
+        let mut store = Store::new();
+        let buf = &mut [0; 4];
+        let data = Data::from_words(&[1, 2], buf).unwrap();
+        store.set_input_registers(2, data).unwrap();
+        let buf = &mut [0; 1];
+        let inputs = Coils::from_bools(&[true, true], buf).unwrap();
+        store.set_discrete_inputs(6, inputs).unwrap();
+
+        let buf = &mut [0; 8];
+        let data = store.read_input_registers(0, 4, buf).unwrap();
+        let mut words = [0; 4];
+        words.iter_mut().zip(data).for_each(|(w, d)| *w = d);
+        assert_eq!(words, [0, 0, 1, 2]);
+        let buf = &mut [0; 1];
+        store.read_discrete_inputs(0, 8, buf).unwrap();
+        assert_eq!(buf[0], 0b1100_0000);
+        assert_eq!(store.take_changes(Table::InputRegisters), None);
+        assert_eq!(store.take_changes(Table::DiscreteInputs), None);
+    }
+
+    #[test]
+    fn out_of_range_access() {
+        let mut store = Store::new();
+        store.set_base_address(Table::HoldingRegisters, 100);
+        let buf = &mut [0; 8];
+        assert_eq!(
+            store.read_holding_registers(99, 1, buf).err(),
+            Some(Exception::IllegalDataAddress)
+        );
+        assert_eq!(
+            store.read_holding_registers(101, 4, buf).err(),
+            Some(Exception::IllegalDataAddress)
+        );
+        assert!(store.read_holding_registers(100, 4, buf).is_ok());
+        assert_eq!(
+            store.write_single_coil(16, true),
+            Err(Exception::IllegalDataAddress)
+        );
+        assert_eq!(
+            store.read_input_registers(0xFFFF, 1, buf).err(),
+            Some(Exception::IllegalDataAddress)
+        );
+        let data_buf = &mut [0; 2];
+        let data = Data::from_words(&[1], data_buf).unwrap();
+        assert_eq!(
+            store.read_write_multiple_registers(100, 5, 100, data, buf),
+            Err(Exception::IllegalDataAddress)
+        );
+        assert_eq!(store.take_changes(Table::HoldingRegisters), None);
+    }
+
+    #[test]
+    fn record_changes() {
+        let mut store = Store::new();
+        store.set_base_address(Table::HoldingRegisters, 40);
+        assert_eq!(store.take_changes(Table::HoldingRegisters), None);
+        store.write_single_register(41, 1).unwrap();
+        assert_eq!(store.take_changes(Table::HoldingRegisters), Some((41, 1)));
+        assert_eq!(store.take_changes(Table::HoldingRegisters), None);
+
+        let buf = &mut [0; 4];
+        let data = Data::from_words(&[1, 2], buf).unwrap();
+        store.write_multiple_registers(42, data).unwrap();
+        store.write_single_register(40, 1).unwrap();
+        assert_eq!(store.take_changes(Table::HoldingRegisters), Some((40, 4)));
+
+        let buf = &mut [0; 2];
+        let data = Data::from_words(&[7], buf).unwrap();
+        store.set_holding_registers(40, data).unwrap();
+        assert_eq!(store.take_changes(Table::HoldingRegisters), None);
+
+        store.write_single_coil(3, true).unwrap();
+        assert_eq!(store.take_changes(Table::Coils), Some((3, 1)));
+    }
+
+    #[test]
+    fn merge_disjoint_changes() {
+        let mut store = Store::new();
+        store.write_single_register(0, 1).unwrap();
+        store.write_single_register(3, 1).unwrap();
+        assert_eq!(store.take_changes(Table::HoldingRegisters), Some((0, 4)));
+
+        let buf = &mut [0; 2];
+        let data = Data::from_words(&[7], buf).unwrap();
+        store.set_input_registers(1, data).unwrap();
+        assert_eq!(store.take_changes(Table::InputRegisters), None);
+        let buf = &mut [0; 1];
+        let inputs = Coils::from_bools(&[true], buf).unwrap();
+        store.set_discrete_inputs(1, inputs).unwrap();
+        assert_eq!(store.take_changes(Table::DiscreteInputs), None);
+    }
+
+    #[test]
+    fn dispatch_requests() {
+        let mut store = Store::new();
+        let buf = &mut [0; 8];
+        let req = RequestPdu(Request::WriteSingleRegister(1, 0x1234));
+        let rsp = service::dispatch_pdu(req, &mut store, buf);
+        assert_eq!(
+            rsp,
+            ResponsePdu(Ok(Response::WriteSingleRegister(1, 0x1234)))
+        );
+        let req = RequestPdu(Request::ReadFifoQueue(0));
+        let rsp = service::dispatch_pdu(req, &mut store, buf);
+        assert_eq!(
+            rsp,
+            ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::ReadFifoQueue,
+                exception: Exception::IllegalFunction,
+            }))
+        );
+    }
+}
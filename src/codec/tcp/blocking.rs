@@ -0,0 +1,415 @@
+//! A blocking Modbus TCP client (master) on top of [`std::net::TcpStream`].
+use super::*;
+use std::{
+    fmt, io,
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+    vec,
+    vec::Vec,
+};
+
+/// Size of the receive and transmit buffers.
+const BUF_LEN: usize = 7 + MAX_FRAME_LEN;
+
+type ClientResult<T> = core::result::Result<T, ClientError>;
+
+/// Client error
+#[derive(Debug)]
+pub enum ClientError {
+    /// The connection failed or timed out.
+    Io(io::Error),
+    /// The server sent an invalid response.
+    Frame(Error),
+    /// The server responded with an exception.
+    Exception(Exception),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ClientError::Io(err) => write!(f, "I/O error: {}", err),
+            ClientError::Frame(err) => write!(f, "Invalid response: {}", err),
+            ClientError::Exception(ex) => write!(f, "Exception response: {:?}", ex),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+impl From<io::Error> for ClientError {
+    fn from(err: io::Error) -> Self {
+        ClientError::Io(err)
+    }
+}
+
+impl From<Error> for ClientError {
+    fn from(err: Error) -> Self {
+        ClientError::Frame(err)
+    }
+}
+
+/// A synchronous Modbus TCP client.
+///
+/// Every request gets a new transaction ID and responses with
+/// other transaction IDs are discarded.
+#[derive(Debug)]
+pub struct Client {
+    stream: TcpStream,
+    unit_id: UnitId,
+    transaction_id: TransactionId,
+    rx_buf: Vec<u8>,
+}
+
+impl Client {
+    /// Connect to a server.
+    pub fn connect<A: ToSocketAddrs>(addr: A, unit_id: UnitId) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        Ok(Self::from_stream(stream, unit_id))
+    }
+    /// Create a client that uses an already connected stream.
+    pub fn from_stream(stream: TcpStream, unit_id: UnitId) -> Self {
+        Self {
+            stream,
+            unit_id,
+            transaction_id: 0,
+            rx_buf: Vec::with_capacity(BUF_LEN),
+        }
+    }
+    /// Set the unit ID of the following requests.
+    pub fn set_unit_id(&mut self, unit_id: UnitId) {
+        self.unit_id = unit_id;
+    }
+    /// Set the timeout of reading a response.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_read_timeout(timeout)
+    }
+    /// Set the timeout of writing a request.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.stream.set_write_timeout(timeout)
+    }
+
+    /// Read multiple coils (0x01)
+    pub fn read_coils(&mut self, address: Address, cnt: Quantity) -> ClientResult<Vec<Coil>> {
+        self.call(Request::ReadCoils(address, cnt), |rsp| match rsp {
+            Response::ReadCoils(coils) => coils.into_iter().collect(),
+            _ => unreachable!(),
+        })
+    }
+    /// Read multiple discrete inputs (0x02)
+    pub fn read_discrete_inputs(
+        &mut self,
+        address: Address,
+        cnt: Quantity,
+    ) -> ClientResult<Vec<Coil>> {
+        self.call(Request::ReadDiscreteInputs(address, cnt), |rsp| match rsp {
+            Response::ReadDiscreteInputs(coils) => coils.into_iter().collect(),
+            _ => unreachable!(),
+        })
+    }
+    /// Read multiple holding registers (0x03)
+    pub fn read_holding_registers(
+        &mut self,
+        address: Address,
+        cnt: Quantity,
+    ) -> ClientResult<Vec<Word>> {
+        self.call(
+            Request::ReadHoldingRegisters(address, cnt),
+            |rsp| match rsp {
+                Response::ReadHoldingRegisters(data) => data.into_iter().collect(),
+                _ => unreachable!(),
+            },
+        )
+    }
+    /// Read multiple input registers (0x04)
+    pub fn read_input_registers(
+        &mut self,
+        address: Address,
+        cnt: Quantity,
+    ) -> ClientResult<Vec<Word>> {
+        self.call(Request::ReadInputRegisters(address, cnt), |rsp| match rsp {
+            Response::ReadInputRegisters(data) => data.into_iter().collect(),
+            _ => unreachable!(),
+        })
+    }
+    /// Write a single coil (0x05)
+    pub fn write_single_coil(&mut self, address: Address, coil: Coil) -> ClientResult<()> {
+        self.call(Request::WriteSingleCoil(address, coil), |_| ())
+    }
+    /// Write a single holding register (0x06)
+    pub fn write_single_register(&mut self, address: Address, word: Word) -> ClientResult<()> {
+        self.call(Request::WriteSingleRegister(address, word), |_| ())
+    }
+    /// Write multiple coils (0x0F)
+    pub fn write_multiple_coils(&mut self, address: Address, coils: &[Coil]) -> ClientResult<()> {
+        let mut buf = vec![0; packed_coils_len(coils.len())];
+        let coils = Coils::from_bools(coils, &mut buf)?;
+        self.call(Request::WriteMultipleCoils(address, coils), |_| ())
+    }
+    /// Write multiple holding registers (0x10)
+    pub fn write_multiple_registers(
+        &mut self,
+        address: Address,
+        words: &[Word],
+    ) -> ClientResult<()> {
+        let mut buf = vec![0; words.len() * 2];
+        let data = Data::from_words(words, &mut buf)?;
+        self.call(Request::WriteMultipleRegisters(address, data), |_| ())
+    }
+    /// Modify a holding register with an AND and an OR mask (0x16)
+    pub fn mask_write_register(
+        &mut self,
+        address: Address,
+        and_mask: AndMask,
+        or_mask: OrMask,
+    ) -> ClientResult<()> {
+        self.call(
+            Request::MaskWriteRegister(address, and_mask, or_mask),
+            |_| (),
+        )
+    }
+    /// Write and read multiple holding registers in a single transaction (0x17)
+    pub fn read_write_multiple_registers(
+        &mut self,
+        read_address: Address,
+        read_cnt: Quantity,
+        write_address: Address,
+        words: &[Word],
+    ) -> ClientResult<Vec<Word>> {
+        let mut buf = vec![0; words.len() * 2];
+        let data = Data::from_words(words, &mut buf)?;
+        let req = Request::ReadWriteMultipleRegisters(read_address, read_cnt, write_address, data);
+        self.call(req, |rsp| match rsp {
+            Response::ReadWriteMultipleRegisters(data) => data.into_iter().collect(),
+            _ => unreachable!(),
+        })
+    }
+
+    /// Send a request and wait for the matching response.
+    ///
+    /// The (already validated) response is passed to `f`.
+    /// A response with an invalid MBAP header fails with
+    /// [`ClientError::Frame`] and all received bytes are discarded.
+    pub fn call<T, F>(&mut self, req: Request, f: F) -> ClientResult<T>
+    where
+        F: FnOnce(Response) -> T,
+    {
+        self.transaction_id = self.transaction_id.wrapping_add(1);
+        let hdr = Header {
+            transaction_id: self.transaction_id,
+            unit_id: self.unit_id,
+        };
+        let mut tx_buf = [0; BUF_LEN];
+        let len = client::encode_request(
+            RequestAdu {
+                hdr,
+                pdu: RequestPdu(req),
+            },
+            &mut tx_buf,
+        )?;
+        self.stream.write_all(&tx_buf[..len])?;
+
+        loop {
+            let (frame, location) = match client::decode_response_frame(&self.rx_buf) {
+                Ok(Some(frame)) => frame,
+                Ok(None) => {
+                    self.receive()?;
+                    continue;
+                }
+                Err(err) => {
+                    self.rx_buf.clear();
+                    return Err(err.into());
+                }
+            };
+            let end = location.start + location.size;
+            if frame.transaction_id != hdr.transaction_id {
+                warn!(
+                    "Discarding stale response with transaction ID {} (expected {})",
+                    frame.transaction_id, hdr.transaction_id
+                );
+                self.rx_buf.drain(..end);
+                continue;
+            }
+            let res = match ResponsePdu::try_from(frame.pdu) {
+                Ok(ResponsePdu(Err(ex))) => Err(ClientError::Exception(ex.exception)),
+                Ok(ResponsePdu(Ok(_))) => Response::decode_for(&req, frame.pdu)
+                    .map(f)
+                    .map_err(ClientError::Frame),
+                Err(err) => Err(ClientError::Frame(err)),
+            };
+            self.rx_buf.drain(..end);
+            return res;
+        }
+    }
+
+    fn receive(&mut self) -> io::Result<()> {
+        let mut buf = [0; BUF_LEN];
+        let cnt = self.stream.read(&mut buf)?;
+        if cnt == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.rx_buf.extend_from_slice(&buf[..cnt]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, thread};
+
+    /// Serve a single connection with a function that creates the
+    /// response bytes for every request.
+    fn serve<F>(respond: F) -> std::net::SocketAddr
+    where
+        F: Fn(RequestAdu, &mut Vec<u8>) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut rx_buf = Vec::new();
+            let mut buf = [0; BUF_LEN];
+            loop {
+                let cnt = match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => return,
+                    Ok(cnt) => cnt,
+                };
+                rx_buf.extend_from_slice(&buf[..cnt]);
+                let mut tx_buf = Vec::new();
+                if let Some((_, location)) = decode(DecoderType::Request, &rx_buf).unwrap() {
+                    let adu = server::decode_request(&rx_buf).unwrap().unwrap();
+                    respond(adu, &mut tx_buf);
+                    rx_buf.drain(..location.start + location.size);
+                }
+                stream.write_all(&tx_buf).unwrap();
+            }
+        });
+        addr
+    }
+
+    fn encode(hdr: Header, rsp: core::result::Result<Response, Exception>, buf: &mut Vec<u8>) {
+        let pdu = ResponsePdu(rsp.map_err(|exception| ExceptionResponse {
+            function: FnCode::ReadHoldingRegisters,
+            exception,
+        }));
+        let mut tx_buf = [0; BUF_LEN];
+        let len = server::encode_response(ResponseAdu { hdr, pdu }, &mut tx_buf).unwrap();
+        buf.extend_from_slice(&tx_buf[..len]);
+    }
+
+    fn registers_server() -> std::net::SocketAddr {
+        serve(|adu, tx_buf| match adu.pdu.0 {
+            Request::ReadHoldingRegisters(address, cnt) => {
+                let words: Vec<Word> = (address..address + cnt).collect();
+                let mut buf = vec![0; words.len() * 2];
+                let data = Data::from_words(&words, &mut buf).unwrap();
+                encode(adu.hdr, Ok(Response::ReadHoldingRegisters(data)), tx_buf);
+            }
+            Request::WriteMultipleCoils(address, coils) => {
+                let rsp = Response::WriteMultipleCoils(address, coils.len() as Quantity);
+                encode(adu.hdr, Ok(rsp), tx_buf);
+            }
+            _ => encode(adu.hdr, Err(Exception::IllegalFunction), tx_buf),
+        })
+    }
+
+    #[test]
+    fn read_holding_registers() {
+        let mut client = Client::connect(registers_server(), 0x12).unwrap();
+        let words = client.read_holding_registers(0x10, 3).unwrap();
+        assert_eq!(words, &[0x10, 0x11, 0x12]);
+        let words = client.read_holding_registers(0x20, 1).unwrap();
+        assert_eq!(words, &[0x20]);
+        assert_eq!(client.transaction_id, 2);
+    }
+
+    #[test]
+    fn write_multiple_coils() {
+        let mut client = Client::connect(registers_server(), 0x12).unwrap();
+        client
+            .write_multiple_coils(0x10, &[true, false, true])
+            .unwrap();
+    }
+
+    #[test]
+    fn exception_response() {
+        let mut client = Client::connect(registers_server(), 0x12).unwrap();
+        match client.read_input_registers(0x10, 1).err().unwrap() {
+            ClientError::Exception(ex) => assert_eq!(ex, Exception::IllegalFunction),
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn discard_stale_responses() {
+        let addr = serve(|adu, tx_buf| {
+            let stale = Header {
+                transaction_id: adu.hdr.transaction_id.wrapping_sub(1),
+                unit_id: adu.hdr.unit_id,
+            };
+            encode(stale, Ok(Response::WriteSingleRegister(0, 0)), tx_buf);
+            encode(
+                adu.hdr,
+                Ok(Response::WriteSingleRegister(0x1234, 0xABCD)),
+                tx_buf,
+            );
+        });
+        let mut client = Client::connect(addr, 0x12).unwrap();
+        client.write_single_register(0x1234, 0xABCD).unwrap();
+        client.write_single_register(0x1234, 0xABCD).unwrap();
+    }
+
+    #[test]
+    fn validate_echoed_fields() {
+        let addr = serve(|adu, tx_buf| {
+            encode(
+                adu.hdr,
+                Ok(Response::WriteSingleRegister(0x1234, 0x0000)),
+                tx_buf,
+            );
+        });
+        let mut client = Client::connect(addr, 0x12).unwrap();
+        match client.write_single_register(0x1234, 0xABCD).err().unwrap() {
+            ClientError::Frame(err) => assert_eq!(err, Error::ValueMismatch(0xABCD, 0x0000)),
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn invalid_protocol_id() {
+        let addr = serve(|adu, tx_buf| {
+            encode(
+                adu.hdr,
+                Ok(Response::WriteSingleRegister(0x1234, 0xABCD)),
+                tx_buf,
+            );
+            tx_buf[3] = 0x01;
+        });
+        let mut client = Client::connect(addr, 0x12).unwrap();
+        // Fail instead of blocking forever if the frame is not rejected.
+        client
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        match client.write_single_register(0x1234, 0xABCD).err().unwrap() {
+            ClientError::Frame(err) => assert_eq!(err, Error::ProtocolNotModbus(1)),
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn read_timeout() {
+        let addr = serve(|_, _| {});
+        let mut client = Client::connect(addr, 0x12).unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        match client.read_holding_registers(0x10, 1).err().unwrap() {
+            ClientError::Io(err) => assert!(matches!(
+                err.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            )),
+            err => panic!("Unexpected error: {}", err),
+        }
+    }
+}
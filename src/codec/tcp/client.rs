@@ -0,0 +1,239 @@
+//! Modbus TCP client (master) specific functions.
+use super::*;
+
+/// Encode an TCP request.
+pub fn encode_request(adu: RequestAdu, buf: &mut [u8]) -> Result<usize> {
+    let RequestAdu { hdr, pdu } = adu;
+    if buf.len() < 7 {
+        return Err(Error::BufferSize);
+    }
+    BigEndian::write_u16(&mut buf[0..2], hdr.transaction_id);
+    BigEndian::write_u16(&mut buf[2..4], 0); //MODBUS Protocol
+    buf[6] = hdr.unit_id;
+    let len = pdu.encode(&mut buf[7..])?;
+    if buf.len() < len + 7 {
+        return Err(Error::BufferSize);
+    }
+    BigEndian::write_u16(&mut buf[4..6], (len + 1) as u16);
+
+    Ok(len + 7)
+}
+
+/// Decode an TCP response.
+///
+/// A response with an invalid protocol id or length field at the start
+/// of the buffer is reported as [`Error::ProtocolNotModbus`] or
+/// [`Error::LengthMismatch`] instead of being skipped.
+pub fn decode_response(buf: &[u8]) -> Result<Option<ResponseAdu<'_>>> {
+    decode_response_frame(buf).and_then(|frame| {
+        if let Some((
+            DecodedFrame {
+                transaction_id,
+                unit_id,
+                pdu,
+            },
+            _frame_pos,
+        )) = frame
+        {
+            let hdr = Header {
+                transaction_id,
+                unit_id,
+            };
+            ResponsePdu::try_from(pdu).map(|pdu| Some(ResponseAdu { hdr, pdu }))
+        } else {
+            Ok(None)
+        }
+    })
+}
+
+/// Decode the next response frame and check the MBAP header
+/// of the frame at the start of the buffer.
+pub(crate) fn decode_response_frame(
+    buf: &[u8],
+) -> Result<Option<(DecodedFrame<'_>, FrameLocation)>> {
+    if let Ok(Some(pdu_len)) = response_pdu_len(buf) {
+        extract_frame(buf, pdu_len)?;
+    }
+    decode(DecoderType::Response, buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_read_holding_registers_request() {
+        let adu = RequestAdu {
+            hdr: Header {
+                transaction_id: 42,
+                unit_id: 0x12,
+            },
+            pdu: RequestPdu(Request::ReadHoldingRegisters(0x082B, 2)),
+        };
+        let buf = &mut [0; 100];
+        let len = encode_request(adu, buf).unwrap();
+        assert_eq!(len, 12);
+        assert_eq!(buf[0], 0x00);
+        assert_eq!(buf[1], 0x2a);
+        assert_eq!(buf[2], 0x00);
+        assert_eq!(buf[3], 0x00);
+        assert_eq!(buf[4], 0x00);
+        assert_eq!(buf[5], 0x06);
+        assert_eq!(buf[6], 0x12);
+        assert_eq!(buf[7], 0x03);
+        assert_eq!(buf[8], 0x08);
+        assert_eq!(buf[9], 0x2B);
+        assert_eq!(buf[10], 0x00);
+        assert_eq!(buf[11], 0x02);
+    }
+
+    #[test]
+    fn encode_request_with_too_small_buffer() {
+        let adu = RequestAdu {
+            hdr: Header {
+                transaction_id: 42,
+                unit_id: 0x12,
+            },
+            pdu: RequestPdu(Request::ReadHoldingRegisters(0x082B, 2)),
+        };
+        assert!(encode_request(adu, &mut [0; 6]).is_err());
+        assert!(encode_request(adu, &mut [0; 11]).is_err());
+    }
+
+    #[test]
+    fn decode_empty_response() {
+        let rsp = decode_response(&[]).unwrap();
+        assert!(rsp.is_none());
+    }
+
+    #[test]
+    fn decode_partly_received_response() {
+        let buf = &[
+            0x00, // transaction id
+            0x2a, // transaction id
+            0x00, // protocol id
+            0x00, // protocol id
+            0x00, // length
+            0x07, // length
+            0x12, // unit id
+            0x03, // function code
+            0x04, // byte count
+            0x89, //
+        ];
+        let rsp = decode_response(buf).unwrap();
+        assert!(rsp.is_none());
+    }
+
+    #[test]
+    fn decode_read_holding_registers_response() {
+        let buf = &[
+            0x00, // transaction id
+            0x2a, // transaction id
+            0x00, // protocol id
+            0x00, // protocol id
+            0x00, // length
+            0x07, // length
+            0x12, // unit id
+            0x03, // function code
+            0x04, // byte count
+            0x89, //
+            0x02, //
+            0x42, //
+            0xC7, //
+        ];
+        let ResponseAdu { hdr, pdu } = decode_response(buf).unwrap().unwrap();
+        assert_eq!(hdr.transaction_id, 42);
+        assert_eq!(hdr.unit_id, 0x12);
+        let ResponsePdu(rsp) = pdu;
+        if let Response::ReadHoldingRegisters(data) = rsp.unwrap() {
+            assert_eq!(data.get(0), Some(0x8902));
+            assert_eq!(data.get(1), Some(0x42C7));
+            assert_eq!(data.get(2), None);
+        } else {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn decode_exception_response() {
+        let buf = &[
+            0x00, // transaction id
+            0x2a, // transaction id
+            0x00, // protocol id
+            0x00, // protocol id
+            0x00, // length
+            0x03, // length
+            0x12, // unit id
+            0x83, // exception function code
+            0x02, // exception code
+        ];
+        let ResponseAdu { hdr, pdu } = decode_response(buf).unwrap().unwrap();
+        assert_eq!(hdr.transaction_id, 42);
+        assert_eq!(hdr.unit_id, 0x12);
+        assert_eq!(
+            pdu,
+            ResponsePdu(Err(ExceptionResponse {
+                function: FnCode::ReadHoldingRegisters,
+                exception: Exception::IllegalDataAddress,
+            }))
+        );
+    }
+
+    #[test]
+    fn decode_wrong_protocol() {
+        let buf = &[
+            0x00, // transaction id
+            0x2a, // transaction id
+            0x00, // protocol id
+            0x01, // protocol id
+            0x00, // length
+            0x06, // length
+            0x12, // unit id
+            0x06, // function code
+            0x22, // addr
+            0x22, // addr
+            0xAB, // value
+            0xCD, // value
+        ];
+        assert_eq!(
+            decode_response(buf).err().unwrap(),
+            Error::ProtocolNotModbus(1)
+        );
+    }
+
+    #[test]
+    fn decode_length_mismatch() {
+        let buf = &[
+            0x00, // transaction id
+            0x2a, // transaction id
+            0x00, // protocol id
+            0x00, // protocol id
+            0x00, // length
+            0x09, // length
+            0x12, // unit id
+            0x06, // function code
+            0x22, // addr
+            0x22, // addr
+            0xAB, // value
+            0xCD, // value
+        ];
+        assert_eq!(
+            decode_response(buf).err().unwrap(),
+            Error::LengthMismatch(9, 6)
+        );
+    }
+
+    #[test]
+    fn decode_response_encoded_by_server() {
+        let rsp_adu = ResponseAdu {
+            hdr: Header {
+                transaction_id: 0x1234,
+                unit_id: 0x05,
+            },
+            pdu: ResponsePdu(Ok(Response::WriteSingleRegister(0x2222, 0xABCD))),
+        };
+        let buf = &mut [0; 100];
+        let len = server::encode_response(rsp_adu, buf).unwrap();
+        assert_eq!(decode_response(&buf[..len]).unwrap().unwrap(), rsp_adu);
+    }
+}
@@ -3,7 +3,12 @@
 use super::*;
 use byteorder::{BigEndian, ByteOrder};
 
+#[cfg(feature = "std")]
+pub mod blocking;
+pub mod client;
 pub mod server;
+#[cfg(feature = "tokio-codec")]
+pub mod tokio_codec;
 pub use crate::frame::tcp::*;
 
 // [MODBUS MESSAGING ON TCP/IP IMPLEMENTATION GUIDE V1.0b](http://modbus.org/docs/Modbus_Messaging_Implementation_Guide_V1_0b.pdf), page 18
@@ -31,7 +36,7 @@ pub struct FrameLocation {
 pub fn decode(
     decoder_type: DecoderType,
     buf: &[u8],
-) -> Result<Option<(DecodedFrame, FrameLocation)>> {
+) -> Result<Option<(DecodedFrame<'_>, FrameLocation)>> {
     use DecoderType::*;
     let mut drop_cnt = 0;
 
@@ -90,7 +95,7 @@ pub fn decode(
 }
 
 /// Extract a PDU frame out of a buffer.
-pub fn extract_frame(buf: &[u8], pdu_len: usize) -> Result<Option<DecodedFrame>> {
+pub fn extract_frame(buf: &[u8], pdu_len: usize) -> Result<Option<DecodedFrame<'_>>> {
     let adu_len = 7 + pdu_len;
     if buf.len() >= adu_len {
         let (adu_buf, _next_frame) = buf.split_at(adu_len);
@@ -98,12 +103,12 @@ pub fn extract_frame(buf: &[u8], pdu_len: usize) -> Result<Option<DecodedFrame>>
         let (transaction_buf, adu_buf) = adu_buf.split_at(2);
         let (protocol_buf, adu_buf) = adu_buf.split_at(2);
         let (length_buf, adu_buf) = adu_buf.split_at(2);
-        let protocol_id = BigEndian::read_u16(&protocol_buf);
+        let protocol_id = BigEndian::read_u16(protocol_buf);
         if protocol_id != 0 {
             return Err(Error::ProtocolNotModbus(protocol_id));
         }
-        let transaction = BigEndian::read_u16(&transaction_buf);
-        let m_length = BigEndian::read_u16(&length_buf) as usize;
+        let transaction = BigEndian::read_u16(transaction_buf);
+        let m_length = BigEndian::read_u16(length_buf) as usize;
         let unit = adu_buf[0];
         if m_length != pdu_len + 1 {
             return Err(Error::LengthMismatch(m_length, pdu_len + 1));
@@ -127,9 +132,18 @@ pub fn request_pdu_len(adu_buf: &[u8]) -> Result<Option<usize>> {
     let len = match fn_code {
         0x01..=0x06 => Some(5),
         0x07 | 0x0B | 0x0C | 0x11 => Some(1),
+        0x08 => Some(5),
         0x0F | 0x10 => {
-            if adu_buf.len() > 10 {
-                Some(6 + adu_buf[10] as usize)
+            if adu_buf.len() > 12 {
+                Some(6 + adu_buf[12] as usize)
+            } else {
+                // incomplete frame
+                None
+            }
+        }
+        0x14 | 0x15 => {
+            if adu_buf.len() > 8 {
+                Some(2 + adu_buf[8] as usize)
             } else {
                 // incomplete frame
                 None
@@ -137,6 +151,7 @@ pub fn request_pdu_len(adu_buf: &[u8]) -> Result<Option<usize>> {
         }
         0x16 => Some(7),
         0x18 => Some(3),
+        0x2B => mei_request_pdu_len(&adu_buf[7..])?,
         0x17 => {
             if adu_buf.len() > 16 {
                 Some(10 + adu_buf[16] as usize)
@@ -159,7 +174,7 @@ pub fn response_pdu_len(adu_buf: &[u8]) -> Result<Option<usize>> {
     }
     let fn_code = adu_buf[7];
     let len = match fn_code {
-        0x01..=0x04 | 0x0C | 0x17 => {
+        0x01..=0x04 | 0x0C | 0x11 | 0x14 | 0x15 | 0x17 => {
             if adu_buf.len() > 8 {
                 Some(2 + adu_buf[8] as usize)
             } else {
@@ -167,7 +182,7 @@ pub fn response_pdu_len(adu_buf: &[u8]) -> Result<Option<usize>> {
                 None
             }
         }
-        0x05 | 0x06 | 0x0B | 0x0F | 0x10 => Some(5),
+        0x05 | 0x06 | 0x08 | 0x0B | 0x0F | 0x10 => Some(5),
         0x07 => Some(2),
         0x16 => Some(7),
         0x18 => {
@@ -178,12 +193,50 @@ pub fn response_pdu_len(adu_buf: &[u8]) -> Result<Option<usize>> {
                 None
             }
         }
+        0x2B => mei_response_pdu_len(&adu_buf[7..])?,
         0x81..=0xAB => Some(2),
         _ => return Err(Error::FnCode(fn_code)),
     };
     Ok(len)
 }
 
+/// The TCP transport.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tcp;
+
+impl Transport for Tcp {
+    type Header = Header;
+    const INIT: Self = Self;
+    const MAX_FRAME_LEN: usize = 7 + MAX_FRAME_LEN;
+    const DROP_ON_ERR: usize = MAX_FRAME_LEN - 1;
+
+    fn locate(&mut self, decoder_type: DecoderType, buf: &[u8]) -> Result<Option<(usize, usize)>> {
+        decode(decoder_type, buf).map(|frame| frame.map(|(_, loc)| (loc.start, loc.size)))
+    }
+    fn extract<'a>(&'a mut self, frame: &'a [u8]) -> Result<Option<(Header, &'a [u8])>> {
+        let frame = extract_frame(frame, frame.len() - 7)?;
+        Ok(frame.map(|frame| {
+            let hdr = Header {
+                transaction_id: frame.transaction_id,
+                unit_id: frame.unit_id,
+            };
+            (hdr, frame.pdu)
+        }))
+    }
+    fn encode_request(adu: RequestAdu, buf: &mut [u8]) -> Result<usize> {
+        client::encode_request(adu, buf)
+    }
+    fn encode_response(adu: ResponseAdu, buf: &mut [u8]) -> Result<usize> {
+        server::encode_response(adu, buf)
+    }
+}
+
+/// An incremental decoder for TCP frames with a fixed capacity of `N` bytes.
+///
+/// `N` must be at least 263 bytes, the maximum size of a TCP frame
+/// including the MBAP header.
+pub type FrameDecoder<const N: usize> = super::FrameDecoder<Tcp, N>;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -214,7 +267,8 @@ mod tests {
         buf[7] = 0x07;
         assert_eq!(request_pdu_len(buf).unwrap(), Some(1));
 
-        // TODO: 0x08
+        buf[7] = 0x08;
+        assert_eq!(request_pdu_len(buf).unwrap(), Some(5));
 
         buf[7] = 0x0B;
         assert_eq!(request_pdu_len(buf).unwrap(), Some(1));
@@ -223,19 +277,23 @@ mod tests {
         assert_eq!(request_pdu_len(buf).unwrap(), Some(1));
 
         buf[7] = 0x0F;
-        buf[10] = 99;
+        buf[12] = 99; // byte count
         assert_eq!(request_pdu_len(buf).unwrap(), Some(105));
 
         buf[7] = 0x10;
-        buf[10] = 99;
+        buf[12] = 99; // byte count
         assert_eq!(request_pdu_len(buf).unwrap(), Some(105));
 
         buf[7] = 0x11;
         assert_eq!(request_pdu_len(buf).unwrap(), Some(1));
 
-        // TODO: 0x14
+        buf[7] = 0x14;
+        buf[8] = 99;
+        assert_eq!(request_pdu_len(buf).unwrap(), Some(101));
 
-        // TODO: 0x15
+        buf[7] = 0x15;
+        buf[8] = 99;
+        assert_eq!(request_pdu_len(buf).unwrap(), Some(101));
 
         buf[7] = 0x16;
         assert_eq!(request_pdu_len(buf).unwrap(), Some(7));
@@ -247,7 +305,12 @@ mod tests {
         buf[7] = 0x18;
         assert_eq!(request_pdu_len(buf).unwrap(), Some(3));
 
-        // TODO: 0x2B
+        buf[7] = 0x2B;
+        buf[8] = 0x0E;
+        assert_eq!(request_pdu_len(buf).unwrap(), Some(4));
+
+        buf[8] = 0x0D;
+        assert_eq!(request_pdu_len(buf).err().unwrap(), Error::MeiType(0x0D));
     }
 
     #[test]
@@ -282,7 +345,8 @@ mod tests {
         buf[7] = 0x07;
         assert_eq!(response_pdu_len(buf).unwrap(), Some(2));
 
-        // TODO: 0x08
+        buf[7] = 0x08;
+        assert_eq!(response_pdu_len(buf).unwrap(), Some(5));
 
         buf[7] = 0x0B;
         assert_eq!(response_pdu_len(buf).unwrap(), Some(5));
@@ -296,11 +360,14 @@ mod tests {
         buf[7] = 0x10;
         assert_eq!(response_pdu_len(buf).unwrap(), Some(5));
 
-        // TODO: 0x11
+        buf[7] = 0x11;
+        assert_eq!(response_pdu_len(buf).unwrap(), Some(101));
 
-        // TODO: 0x14
+        buf[7] = 0x14;
+        assert_eq!(response_pdu_len(buf).unwrap(), Some(101));
 
-        // TODO: 0x15
+        buf[7] = 0x15;
+        assert_eq!(response_pdu_len(buf).unwrap(), Some(101));
 
         buf[7] = 0x16;
         assert_eq!(response_pdu_len(buf).unwrap(), Some(7));
@@ -313,7 +380,9 @@ mod tests {
         buf[9] = 0x00; // byte count Lo
         assert_eq!(response_pdu_len(buf).unwrap(), Some(259));
 
-        // TODO: 0x2B
+        buf[7] = 0x2B;
+        buf[8] = 0x0E;
+        assert_eq!(response_pdu_len(buf).unwrap(), None);
 
         for i in 0x81..0xAB {
             buf[7] = i;
@@ -419,4 +488,51 @@ mod tests {
             assert!(decode(DecoderType::Response, buf).is_err());
         }
     }
+
+    mod frame_decoder_state {
+
+        use super::*;
+
+        const READ_HOLDING_REGISTERS_REQ: &[u8] = &[
+            0x00, 0x2A, 0x00, 0x00, 0x00, 0x06, 0x12, 0x03, 0x08, 0x2B, 0x00, 0x02,
+        ];
+
+        #[test]
+        fn decode_requests_pushed_in_parts() {
+            let mut decoder = FrameDecoder::<263>::new();
+            decoder.push(&READ_HOLDING_REGISTERS_REQ[..7]);
+            assert!(decoder.next_request().unwrap().is_none());
+            decoder.push(&READ_HOLDING_REGISTERS_REQ[7..]);
+            decoder.push(READ_HOLDING_REGISTERS_REQ);
+            let adu = decoder.next_request().unwrap().unwrap();
+            assert_eq!(adu.hdr.transaction_id, 42);
+            assert_eq!(adu.hdr.unit_id, 0x12);
+            assert_eq!(
+                adu.pdu,
+                RequestPdu(Request::ReadHoldingRegisters(0x082B, 2))
+            );
+            assert!(decoder.next_request().unwrap().is_some());
+            assert!(decoder.next_request().unwrap().is_none());
+            assert!(decoder.is_empty());
+            assert_eq!(decoder.dropped(), 0);
+        }
+
+        #[test]
+        fn drop_garbage_in_front_of_a_frame() {
+            let mut decoder = FrameDecoder::<263>::new();
+            decoder.push(&[0x00, 0x01, 0x00]);
+            decoder.push(READ_HOLDING_REGISTERS_REQ);
+            assert!(decoder.next_request().unwrap().is_some());
+            assert_eq!(decoder.dropped(), 3);
+        }
+
+        #[test]
+        fn decode_response() {
+            let mut decoder = FrameDecoder::<263>::new();
+            decoder.push(&[0x00, 0x2A, 0x00, 0x00, 0x00, 0x03, 0x12, 0x83, 0x02]);
+            let adu = decoder.next_response().unwrap().unwrap();
+            assert_eq!(adu.hdr.transaction_id, 42);
+            assert!(adu.pdu.0.is_err());
+        }
+    }
 }
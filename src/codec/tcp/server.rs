@@ -1,43 +1,37 @@
 //! Modbus TCP server (slave) specific functions.
 use super::*;
 
+pub use crate::codec::dispatch;
+
 /// Decode an TCP request.
-pub fn decode_request(buf: &[u8]) -> Result<Option<RequestAdu>> {
-    decode(DecoderType::Request, buf)
-        .and_then(|frame| {
-            if let Some((
-                DecodedFrame {
-                    transaction_id,
-                    unit_id,
-                    pdu,
-                },
-                _frame_pos,
-            )) = frame
-            {
-                let hdr = Header {
-                    transaction_id,
-                    unit_id,
-                };
-                // Decoding of the PDU should are unlikely to fail due
-                // to transmission errors, because the frame's bytes
-                // have already been verified at the TCP level.
-                Request::try_from(pdu)
-                    .map(RequestPdu)
-                    .map(|pdu| Some(RequestAdu { hdr, pdu }))
-                    .map_err(|err| {
-                        // Unrecoverable error
-                        error!("Failed to decode request PDU: {}", err);
-                        err
-                    })
-            } else {
-                Ok(None)
-            }
-        })
-        .or_else(|_| {
-            // Decoding the transport frame is non-destructive and must
-            // never fail!
-            unreachable!();
-        })
+///
+/// It returns `Ok(None)` if more bytes are needed.
+/// A [`RequestError::Frame`] means that no valid frame could be found
+/// and the buffered bytes should be discarded.
+/// A [`RequestError::Pdu`] carries the header of a valid frame
+/// with an invalid PDU that should be answered with an exception.
+pub fn decode_request(
+    buf: &[u8],
+) -> core::result::Result<Option<RequestAdu<'_>>, RequestError<Header>> {
+    let (
+        DecodedFrame {
+            transaction_id,
+            unit_id,
+            pdu,
+        },
+        _frame_pos,
+    ) = match decode(DecoderType::Request, buf)? {
+        Some(frame) => frame,
+        None => return Ok(None),
+    };
+    let hdr = Header {
+        transaction_id,
+        unit_id,
+    };
+    // The frame's bytes have already been verified at the TCP level,
+    // so an invalid PDU is not caused by transmission errors.
+    let pdu = decode_request_pdu(hdr, pdu)?;
+    Ok(Some(RequestAdu { hdr, pdu }))
 }
 
 /// Encode an TCP response.
@@ -121,6 +115,53 @@ mod tests {
         assert!(decode_request(buf).unwrap().is_none());
     }
 
+    #[test]
+    fn decode_garbage() {
+        let buf = &[0x01; MAX_FRAME_LEN + 16];
+        assert!(matches!(decode_request(buf), Err(RequestError::Frame(_))));
+        assert_eq!(decode_request(buf).unwrap_err().exception_response(), None);
+    }
+
+    #[test]
+    fn decode_invalid_request_pdu() {
+        let buf = &[
+            0x00, // Transaction id
+            0x2a, // Transaction id
+            0x00, // Protocol id
+            0x00, // Protocol id
+            0x00, // length
+            0x06, // length
+            0x12, // unit id
+            0x03, // function code
+            0x00, // addr
+            0x00, // addr
+            0x00, // quantity
+            0x00, // quantity
+        ];
+        let hdr = Header {
+            transaction_id: 42,
+            unit_id: 0x12,
+        };
+        let err = decode_request(buf).unwrap_err();
+        assert_eq!(
+            err,
+            RequestError::Pdu {
+                hdr,
+                fn_code: 0x03,
+                error: Error::Quantity(0),
+            }
+        );
+        let (rsp_hdr, ex) = err.exception_response().unwrap();
+        assert_eq!(rsp_hdr, hdr);
+        assert_eq!(
+            ex,
+            ExceptionResponse {
+                function: FnCode::ReadHoldingRegisters,
+                exception: Exception::IllegalDataValue,
+            }
+        );
+    }
+
     #[test]
     fn encode_write_single_register_response() {
         let adu = ResponseAdu {
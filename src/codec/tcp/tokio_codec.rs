@@ -0,0 +1,102 @@
+//! [`tokio_util::codec`] implementations for Modbus TCP.
+use super::*;
+
+/// A TCP request ADU that owns its bytes.
+pub type OwnedRequestAdu = crate::codec::tokio_codec::OwnedRequestAdu<Header>;
+
+/// A TCP response ADU that owns its bytes.
+pub type OwnedResponseAdu = crate::codec::tokio_codec::OwnedResponseAdu<Header>;
+
+/// Client (master) codec that encodes requests and decodes responses.
+pub type ClientCodec = crate::codec::tokio_codec::ClientCodec<Tcp>;
+
+/// Server (slave) codec that decodes requests and encodes responses.
+pub type ServerCodec = crate::codec::tokio_codec::ServerCodec<Tcp>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::BytesMut;
+    use futures::{SinkExt, StreamExt};
+    use tokio_util::codec::{Decoder, Framed};
+
+    #[test]
+    fn decode_frames_and_advance_the_buffer() {
+        let mut codec = ServerCodec::default();
+        let mut buf = BytesMut::from(
+            &[
+                0x00, 0x2A, 0x00, 0x00, 0x00, 0x06, 0x12, 0x03, 0x08, 0x2B, 0x00, 0x02, 0x00, 0x2B,
+                0x00,
+            ][..],
+        );
+        let adu = codec.decode(&mut buf).unwrap().unwrap().unwrap();
+        assert_eq!(adu.hdr.transaction_id, 42);
+        assert_eq!(
+            adu.adu().unwrap().pdu,
+            RequestPdu(Request::ReadHoldingRegisters(0x082B, 2))
+        );
+        assert_eq!(&buf[..], &[0x00, 0x2B, 0x00]);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert_eq!(buf.len(), 3);
+    }
+
+    #[test]
+    fn decode_invalid_request_pdu() {
+        let mut codec = ServerCodec::default();
+        let mut buf = BytesMut::from(
+            &[
+                0x00, 0x2A, 0x00, 0x00, 0x00, 0x06, 0x12, 0x05, 0x00, 0x01, 0x12, 0x34,
+            ][..],
+        );
+        let err = codec.decode(&mut buf).unwrap().unwrap().unwrap_err();
+        let hdr = Header {
+            transaction_id: 42,
+            unit_id: 0x12,
+        };
+        assert_eq!(
+            err,
+            RequestError::Pdu {
+                hdr,
+                fn_code: 0x05,
+                error: Error::CoilValue(0x1234),
+            }
+        );
+        let (rsp_hdr, ex) = err.exception_response().unwrap();
+        assert_eq!(rsp_hdr, hdr);
+        assert_eq!(ex.exception, Exception::IllegalDataValue);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn decode_invalid_request_frame() {
+        let mut codec = ServerCodec::default();
+        let mut buf = BytesMut::from(&[0x01; 300][..]);
+        let err = codec.decode(&mut buf).unwrap().unwrap().unwrap_err();
+        assert!(matches!(err, RequestError::Frame(_)));
+        assert_eq!(err.exception_response(), None);
+        assert!(buf.len() < 300);
+    }
+
+    #[tokio::test]
+    async fn client_server_roundtrip() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut client = Framed::new(client, ClientCodec::default());
+        let mut server = Framed::new(server, ServerCodec::default());
+
+        let hdr = Header {
+            transaction_id: 7,
+            unit_id: 0x12,
+        };
+        let req = RequestPdu(Request::ReadHoldingRegisters(0x082B, 2));
+        client.send(RequestAdu { hdr, pdu: req }).await.unwrap();
+        let req_adu = server.next().await.unwrap().unwrap().unwrap();
+        assert_eq!(req_adu.adu().unwrap(), RequestAdu { hdr, pdu: req });
+
+        let buf = &mut [0; 4];
+        let data = Data::from_words(&[0xABCD, 0x1234], buf).unwrap();
+        let rsp = ResponsePdu(Ok(Response::ReadHoldingRegisters(data)));
+        server.send(ResponseAdu { hdr, pdu: rsp }).await.unwrap();
+        let rsp_adu = client.next().await.unwrap().unwrap();
+        assert_eq!(rsp_adu.adu().unwrap(), ResponseAdu { hdr, pdu: rsp });
+    }
+}
@@ -0,0 +1,178 @@
+//! Generic [`tokio_util::codec`] implementations for the stream transports.
+use super::decoder::{RequestAdu, ResponseAdu};
+use super::*;
+use bytes::{Buf, Bytes, BytesMut};
+use std::io;
+
+/// A request ADU that owns its bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedRequestAdu<H> {
+    /// The header of the received frame.
+    pub hdr: H,
+    pdu: Bytes,
+}
+
+impl<H: AduHeader> OwnedRequestAdu<H> {
+    /// The raw bytes of the PDU.
+    pub fn pdu_bytes(&self) -> &Bytes {
+        &self.pdu
+    }
+    /// Decode the ADU that borrows the owned bytes.
+    ///
+    /// The PDU has already been validated by the codec,
+    /// so this only fails if the codec is broken.
+    pub fn adu(&self) -> Result<H::RequestAdu<'_>> {
+        let req = Request::try_from(&self.pdu[..])?;
+        Ok(self.hdr.request_adu(RequestPdu(req)))
+    }
+}
+
+/// A response ADU that owns its bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedResponseAdu<H> {
+    /// The header of the received frame.
+    pub hdr: H,
+    pdu: Bytes,
+}
+
+impl<H: AduHeader> OwnedResponseAdu<H> {
+    /// The raw bytes of the PDU.
+    pub fn pdu_bytes(&self) -> &Bytes {
+        &self.pdu
+    }
+    /// Decode the ADU that borrows the owned bytes.
+    ///
+    /// The PDU has already been validated by the codec,
+    /// so this only fails if the codec is broken.
+    pub fn adu(&self) -> Result<H::ResponseAdu<'_>> {
+        let pdu = ResponsePdu::try_from(&self.pdu[..])?;
+        Ok(self.hdr.response_adu(pdu))
+    }
+}
+
+/// Client (master) codec that encodes requests and decodes responses.
+#[derive(Debug, Clone)]
+pub struct ClientCodec<T> {
+    transport: T,
+}
+
+impl<T: Transport> Default for ClientCodec<T> {
+    fn default() -> Self {
+        Self { transport: T::INIT }
+    }
+}
+
+/// Server (slave) codec that decodes requests and encodes responses.
+///
+/// Invalid frames and PDUs are returned as [`RequestError`] items,
+/// so the server can respond with an exception and keep the stream
+/// open. The error type of the codec is reserved for I/O errors.
+#[derive(Debug, Clone)]
+pub struct ServerCodec<T> {
+    transport: T,
+}
+
+impl<T: Transport> Default for ServerCodec<T> {
+    fn default() -> Self {
+        Self { transport: T::INIT }
+    }
+}
+
+/// Split the next frame off the buffer.
+fn split_frame<T: Transport>(
+    transport: &mut T,
+    decoder_type: DecoderType,
+    src: &mut BytesMut,
+) -> Result<Option<(T::Header, Bytes)>> {
+    match transport.locate(decoder_type, src) {
+        Ok(Some((start, size))) => {
+            src.advance(start);
+            let frame = src.split_to(size).freeze();
+            let (hdr, pdu) = transport.extract(&frame)?.ok_or(Error::BufferSize)?;
+            // The PDU is a part of the frame unless the transport had to decode it.
+            let pdu = if frame.as_ptr_range().contains(&pdu.as_ptr()) {
+                frame.slice_ref(pdu)
+            } else {
+                Bytes::copy_from_slice(pdu)
+            };
+            Ok(Some((hdr, pdu)))
+        }
+        Ok(None) => Ok(None),
+        Err(err) => {
+            // The decoder gave up after dropping these bytes.
+            src.advance(T::DROP_ON_ERR.min(src.len()));
+            Err(err)
+        }
+    }
+}
+
+fn invalid_data(err: Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+fn encode_into<T, F>(dst: &mut BytesMut, encode: F) -> io::Result<()>
+where
+    T: Transport,
+    F: FnOnce(&mut [u8]) -> Result<usize>,
+{
+    let start = dst.len();
+    dst.resize(start + T::MAX_FRAME_LEN, 0);
+    match encode(&mut dst[start..]) {
+        Ok(len) => {
+            dst.truncate(start + len);
+            Ok(())
+        }
+        Err(err) => {
+            dst.truncate(start);
+            Err(invalid_data(err))
+        }
+    }
+}
+
+impl<T: Transport> tokio_util::codec::Decoder for ClientCodec<T> {
+    type Item = OwnedResponseAdu<T::Header>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        let frame = split_frame(&mut self.transport, DecoderType::Response, src);
+        let (hdr, pdu) = match frame.map_err(invalid_data)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+        ResponsePdu::try_from(&pdu[..]).map_err(invalid_data)?;
+        Ok(Some(OwnedResponseAdu { hdr, pdu }))
+    }
+}
+
+impl<'a, T: Transport> tokio_util::codec::Encoder<RequestAdu<'a, T>> for ClientCodec<T> {
+    type Error = io::Error;
+
+    fn encode(&mut self, adu: RequestAdu<'a, T>, dst: &mut BytesMut) -> io::Result<()> {
+        encode_into::<T, _>(dst, |buf| T::encode_request(adu, buf))
+    }
+}
+
+impl<T: Transport> tokio_util::codec::Decoder for ServerCodec<T> {
+    type Item = core::result::Result<OwnedRequestAdu<T::Header>, RequestError<T::Header>>;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<Self::Item>> {
+        let (hdr, pdu) = match split_frame(&mut self.transport, DecoderType::Request, src) {
+            Ok(Some(frame)) => frame,
+            Ok(None) => return Ok(None),
+            Err(err) => return Ok(Some(Err(RequestError::Frame(err)))),
+        };
+        if let Err(err) = decode_request_pdu(hdr, &pdu) {
+            return Ok(Some(Err(err)));
+        }
+        Ok(Some(Ok(OwnedRequestAdu { hdr, pdu })))
+    }
+}
+
+impl<'a, T: Transport> tokio_util::codec::Encoder<ResponseAdu<'a, T>> for ServerCodec<T> {
+    type Error = io::Error;
+
+    fn encode(&mut self, adu: ResponseAdu<'a, T>, dst: &mut BytesMut) -> io::Result<()> {
+        encode_into::<T, _>(dst, |buf| T::encode_response(adu, buf))
+    }
+}
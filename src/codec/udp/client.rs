@@ -0,0 +1,64 @@
+//! Modbus UDP client (master) specific functions.
+use super::*;
+
+pub use crate::codec::tcp::client::encode_request;
+
+/// Decode an UDP response datagram.
+pub fn decode_response(datagram: &[u8]) -> Result<ResponseAdu<'_>> {
+    let DecodedFrame {
+        transaction_id,
+        unit_id,
+        pdu,
+    } = decode(DecoderType::Response, datagram)?;
+    let hdr = Header {
+        transaction_id,
+        unit_id,
+    };
+    ResponsePdu::try_from(pdu).map(|pdu| ResponseAdu { hdr, pdu })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_read_holding_registers_response() {
+        let datagram = &[
+            0x00, 0x2A, // transaction id
+            0x00, 0x00, // protocol id
+            0x00, 0x07, // length
+            0x12, // unit id
+            0x03, // function code
+            0x04, // byte count
+            0x89, 0x02, //
+            0x42, 0xC7, //
+        ];
+        let ResponseAdu { hdr, pdu } = decode_response(datagram).unwrap();
+        assert_eq!(hdr.transaction_id, 42);
+        assert_eq!(hdr.unit_id, 0x12);
+        let ResponsePdu(rsp) = pdu;
+        if let Response::ReadHoldingRegisters(data) = rsp.unwrap() {
+            assert_eq!(data.get(0), Some(0x8902));
+            assert_eq!(data.get(1), Some(0x42C7));
+            assert_eq!(data.get(2), None);
+        } else {
+            unreachable!()
+        }
+        assert!(decode_response(&datagram[..12]).is_err());
+    }
+
+    #[test]
+    fn encode_response_decode_response_roundtrip() {
+        let rsp_adu = ResponseAdu {
+            hdr: Header {
+                transaction_id: 7,
+                unit_id: 0x05,
+            },
+            pdu: ResponsePdu(Ok(Response::WriteSingleRegister(0x2222, 0xABCD))),
+        };
+        let buf = &mut [0; 100];
+        let len = server::encode_response(rsp_adu, buf).unwrap();
+        assert_eq!(decode_response(&buf[..len]).unwrap(), rsp_adu);
+        assert!(decode_response(&buf[..=len]).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! Modbus UDP
+//!
+//! Every datagram contains exactly one ADU with an MBAP header.
+
+use super::*;
+
+pub mod client;
+pub mod server;
+pub use super::tcp::DecodedFrame;
+pub use crate::frame::tcp::*;
+
+/// Size of the MBAP header without the unit ID.
+const MBAP_LEN: usize = 6;
+
+/// Decode a PDU frame out of a single datagram.
+///
+/// In contrast to the stream oriented [`tcp::decode`](super::tcp::decode)
+/// no bytes are dropped: the datagram must contain exactly one frame.
+pub fn decode(decoder_type: DecoderType, datagram: &[u8]) -> Result<DecodedFrame<'_>> {
+    use DecoderType::*;
+
+    if datagram.len() < MBAP_LEN + 2 {
+        return Err(Error::BufferSize);
+    }
+    let protocol_id = BigEndian::read_u16(&datagram[2..4]);
+    if protocol_id != 0 {
+        return Err(Error::ProtocolNotModbus(protocol_id));
+    }
+    let m_length = BigEndian::read_u16(&datagram[4..6]) as usize;
+    if m_length != datagram.len() - MBAP_LEN {
+        return Err(Error::LengthMismatch(m_length, datagram.len() - MBAP_LEN));
+    }
+    let pdu_len = match decoder_type {
+        Request => tcp::request_pdu_len(datagram),
+        Response => tcp::response_pdu_len(datagram),
+    }?;
+    match pdu_len {
+        Some(pdu_len) if pdu_len + 1 == m_length => {
+            tcp::extract_frame(datagram, pdu_len)?.ok_or(Error::BufferSize)
+        }
+        Some(pdu_len) => Err(Error::LengthMismatch(m_length, pdu_len + 1)),
+        None => Err(Error::BufferSize),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_read_holding_registers_request() {
+        let datagram = &[
+            0x00, 0x2A, // transaction id
+            0x00, 0x00, // protocol id
+            0x00, 0x06, // length
+            0x12, // unit id
+            0x03, // function code
+            0x08, 0x2B, // address
+            0x00, 0x02, // quantity
+        ];
+        let frame = decode(DecoderType::Request, datagram).unwrap();
+        assert_eq!(frame.transaction_id, 42);
+        assert_eq!(frame.unit_id, 0x12);
+        assert_eq!(frame.pdu, &datagram[7..]);
+    }
+
+    #[test]
+    fn reject_trailing_bytes() {
+        let datagram = &[
+            0x00, 0x2A, // transaction id
+            0x00, 0x00, // protocol id
+            0x00, 0x06, // length
+            0x12, // unit id
+            0x03, // function code
+            0x08, 0x2B, // address
+            0x00, 0x02, // quantity
+            0x00, // trailing byte
+        ];
+        assert_eq!(
+            decode(DecoderType::Request, datagram).err().unwrap(),
+            Error::LengthMismatch(6, 7)
+        );
+    }
+
+    #[test]
+    fn reject_truncated_datagram() {
+        let datagram = &[
+            0x00, 0x2A, // transaction id
+            0x00, 0x00, // protocol id
+            0x00, 0x06, // length
+            0x12, // unit id
+            0x03, // function code
+            0x08, 0x2B, // address
+            0x00, // quantity (incomplete)
+        ];
+        assert_eq!(
+            decode(DecoderType::Request, datagram).err().unwrap(),
+            Error::LengthMismatch(6, 5)
+        );
+        assert_eq!(
+            decode(DecoderType::Request, &datagram[..7]).err().unwrap(),
+            Error::BufferSize
+        );
+    }
+
+    #[test]
+    fn reject_length_field_that_does_not_match_the_pdu() {
+        let datagram = &[
+            0x00, 0x2A, // transaction id
+            0x00, 0x00, // protocol id
+            0x00, 0x07, // length
+            0x12, // unit id
+            0x03, // function code
+            0x08, 0x2B, // address
+            0x00, 0x02, // quantity
+            0x00, // extra byte
+        ];
+        assert_eq!(
+            decode(DecoderType::Request, datagram).err().unwrap(),
+            Error::LengthMismatch(7, 6)
+        );
+    }
+
+    #[test]
+    fn reject_incomplete_pdu() {
+        let datagram = &[
+            0x00, 0x2A, // transaction id
+            0x00, 0x00, // protocol id
+            0x00, 0x02, // length
+            0x12, // unit id
+            0x10, // function code
+        ];
+        assert_eq!(
+            decode(DecoderType::Request, datagram).err().unwrap(),
+            Error::BufferSize
+        );
+    }
+
+    #[test]
+    fn reject_other_protocols() {
+        let datagram = &[
+            0x00, 0x2A, // transaction id
+            0x00, 0x01, // protocol id
+            0x00, 0x06, // length
+            0x12, // unit id
+            0x03, // function code
+            0x08, 0x2B, // address
+            0x00, 0x02, // quantity
+        ];
+        assert_eq!(
+            decode(DecoderType::Request, datagram).err().unwrap(),
+            Error::ProtocolNotModbus(1)
+        );
+    }
+}
@@ -0,0 +1,41 @@
+//! Modbus UDP server (slave) specific functions.
+use super::*;
+
+pub use crate::codec::{dispatch, tcp::server::encode_response};
+
+/// Decode an UDP request datagram.
+pub fn decode_request(
+    datagram: &[u8],
+) -> core::result::Result<RequestAdu<'_>, RequestError<Header>> {
+    let DecodedFrame {
+        transaction_id,
+        unit_id,
+        pdu,
+    } = decode(DecoderType::Request, datagram)?;
+    let hdr = Header {
+        transaction_id,
+        unit_id,
+    };
+    let pdu = decode_request_pdu(hdr, pdu)?;
+    Ok(RequestAdu { hdr, pdu })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_request_decode_request_roundtrip() {
+        let req_adu = RequestAdu {
+            hdr: Header {
+                transaction_id: 7,
+                unit_id: 0x05,
+            },
+            pdu: RequestPdu(Request::WriteSingleRegister(0x2222, 0xABCD)),
+        };
+        let buf = &mut [0; 100];
+        let len = client::encode_request(req_adu, buf).unwrap();
+        assert_eq!(decode_request(&buf[..len]).unwrap(), req_adu);
+        assert!(decode_request(&buf[..len - 1]).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::frame::Exception;
 use core::fmt;
 
 /// modbus-core Error
@@ -9,18 +10,57 @@ pub enum Error {
     BufferSize,
     /// Invalid function code
     FnCode(u8),
-    /// Invalid exception code
-    ExceptionCode(u8),
     /// Invalid exception function code
     ExceptionFnCode(u8),
     /// Invalid CRC
     Crc(u16, u16),
+    /// Invalid LRC
+    Lrc(u8, u8),
+    /// Invalid hex digit
+    HexDigit(u8),
     /// Invalid byte count
     ByteCount(u8),
     /// Length Mismatch
     LengthMismatch(usize, usize),
     /// Protocol not Modbus
     ProtocolNotModbus(u16),
+    /// Invalid file record reference type
+    ReferenceType(u8),
+    /// Invalid MEI type
+    MeiType(u8),
+    /// Invalid read device ID code
+    ReadDeviceIdCode(u8),
+    /// Invalid sub-function code
+    SubFnCode(u16),
+    /// Invalid diagnostics data
+    DiagnosticsData(u16),
+    /// Invalid FIFO count
+    FifoCount(u16),
+    /// Echoed address does not match the request
+    AddressMismatch(u16, u16),
+    /// Echoed quantity does not match the request
+    QuantityMismatch(u16, u16),
+    /// Echoed value does not match the request
+    ValueMismatch(u16, u16),
+    /// Quantity out of the allowed range
+    Quantity(u16),
+    /// Address range exceeds the address space
+    AddressOverflow(u16, u16),
+    /// Value can not be encoded as BCD
+    Bcd(u32),
+}
+
+impl Error {
+    /// The exception a server should respond with if a request
+    /// could not be decoded because of this error.
+    pub const fn exception(&self) -> Option<Exception> {
+        match self {
+            Error::FnCode(_) => Some(Exception::IllegalFunction),
+            Error::Quantity(_) => Some(Exception::IllegalDataValue),
+            Error::AddressOverflow(_, _) => Some(Exception::IllegalDataAddress),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Error {
@@ -31,13 +71,18 @@ impl fmt::Display for Error {
             CoilValue(v) => write!(f, "Invalid coil value: {}", v),
             BufferSize => write!(f, "Invalid buffer size"),
             FnCode(fn_code) => write!(f, "Invalid function code: 0x{:0>2X}", fn_code),
-            ExceptionCode(code) => write!(f, "Invalid exception code:0x {:0>2X}", code),
             ExceptionFnCode(code) => write!(f, "Invalid exception function code:0x {:0>2X}", code),
             Crc(expected, actual) => write!(
                 f,
                 "Invalid CRC: expected = 0x{:0>4X}, actual = 0x{:0>4X}",
                 expected, actual
             ),
+            Lrc(expected, actual) => write!(
+                f,
+                "Invalid LRC: expected = 0x{:0>2X}, actual = 0x{:0>2X}",
+                expected, actual
+            ),
+            HexDigit(c) => write!(f, "Invalid hex digit: 0x{:0>2X}", c),
             ByteCount(cnt) => write!(f, "Invalid byte count: {}", cnt),
             LengthMismatch(length_field, pdu_len) => write!(
                 f,
@@ -49,6 +94,37 @@ impl fmt::Display for Error {
                 "Protocol not Modbus(0), recieved {} instead",
                 protocol_id
             ),
+            ReferenceType(ref_type) => write!(f, "Invalid reference type: 0x{:0>2X}", ref_type),
+            MeiType(mei_type) => write!(f, "Invalid MEI type: 0x{:0>2X}", mei_type),
+            ReadDeviceIdCode(code) => write!(f, "Invalid read device ID code: 0x{:0>2X}", code),
+            SubFnCode(code) => write!(f, "Invalid sub-function code: 0x{:0>4X}", code),
+            DiagnosticsData(data) => write!(f, "Invalid diagnostics data: 0x{:0>4X}", data),
+            FifoCount(cnt) => write!(f, "Invalid FIFO count: {}", cnt),
+            AddressMismatch(expected, actual) => write!(
+                f,
+                "Address Mismatch: expected = 0x{:0>4X}, actual = 0x{:0>4X}",
+                expected, actual
+            ),
+            QuantityMismatch(expected, actual) => write!(
+                f,
+                "Quantity Mismatch: expected = {}, actual = {}",
+                expected, actual
+            ),
+            ValueMismatch(expected, actual) => write!(
+                f,
+                "Value Mismatch: expected = 0x{:0>4X}, actual = 0x{:0>4X}",
+                expected, actual
+            ),
+            Quantity(quantity) => write!(f, "Invalid quantity: {}", quantity),
+            AddressOverflow(address, quantity) => write!(
+                f,
+                "Address overflow: address = 0x{:0>4X}, quantity = {}",
+                address, quantity
+            ),
+            Bcd(value) => write!(f, "Value can not be encoded as BCD: {}", value),
         }
     }
 }
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
@@ -11,12 +11,22 @@ pub struct Coils<'c> {
 impl<'c> Coils<'c> {
     /// Pack coils defined by an bool slice into a byte buffer.
     pub fn from_bools(bools: &[bool], target: &'c mut [u8]) -> Result<Self, Error> {
-        pack_coils(bools, target)?;
+        let packed_len = pack_coils(bools, target)?;
         Ok(Coils {
-            data: target,
+            data: &target[..packed_len],
             quantity: bools.len(),
         })
     }
+    /// Use already packed coils.
+    ///
+    /// The length of `data` has to match the number of bytes
+    /// that is required to pack `quantity` coils.
+    pub fn new(data: &'c [u8], quantity: usize) -> Result<Self, Error> {
+        if data.len() != packed_coils_len(quantity) {
+            return Err(Error::BufferSize);
+        }
+        Ok(Coils { data, quantity })
+    }
     //TODO: add tests
     pub(crate) fn copy_to(&self, buf: &mut [u8]) {
         let packed_len = self.packed_len();
@@ -76,6 +86,95 @@ impl<'c> IntoIterator for Coils<'c> {
     }
 }
 
+/// Mutable packed coils in a caller provided buffer.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CoilsMut<'c> {
+    pub(crate) data: &'c mut [u8],
+    pub(crate) quantity: usize,
+}
+
+impl<'c> CoilsMut<'c> {
+    /// Use a byte buffer to hold `quantity` coils that are initially off.
+    pub fn new(target: &'c mut [u8], quantity: usize) -> Result<Self, Error> {
+        let packed_len = packed_coils_len(quantity);
+        if target.len() < packed_len {
+            return Err(Error::BufferSize);
+        }
+        let data = &mut target[..packed_len];
+        data.fill(0);
+        Ok(CoilsMut { data, quantity })
+    }
+    /// Quantity of coils
+    pub const fn len(&self) -> usize {
+        self.quantity
+    }
+    ///  Returns `true` if the container has no items.
+    pub const fn is_empty(&self) -> bool {
+        self.quantity == 0
+    }
+    /// Get a specific coil.
+    pub fn get(&self, idx: usize) -> Option<Coil> {
+        self.as_coils().get(idx)
+    }
+    /// Set a specific coil.
+    pub fn set(&mut self, idx: usize, coil: Coil) -> Result<(), Error> {
+        if idx >= self.quantity {
+            return Err(Error::BufferSize);
+        }
+        let mask = 1 << (idx % 8);
+        if coil {
+            self.data[idx / 8] |= mask;
+        } else {
+            self.data[idx / 8] &= !mask;
+        }
+        Ok(())
+    }
+    /// Set all coils to the same state.
+    pub fn fill(&mut self, coil: Coil) {
+        self.data.fill(if coil { 0xFF } else { 0x00 });
+        // Keep the unused bits of the last byte cleared.
+        let rest = self.quantity % 8;
+        if rest > 0 {
+            if let Some(last) = self.data.last_mut() {
+                *last &= (1 << rest) - 1;
+            }
+        }
+    }
+    /// Copy all coils from `src` that has to be of the same length.
+    pub fn copy_from(&mut self, src: Coils) -> Result<(), Error> {
+        if src.len() != self.quantity {
+            return Err(Error::BufferSize);
+        }
+        self.data.copy_from_slice(&src.data[..self.data.len()]);
+        Ok(())
+    }
+    /// Set consecutive coils beginning with the one at index `start`.
+    pub fn set_range(&mut self, start: usize, coils: &[Coil]) -> Result<(), Error> {
+        match start.checked_add(coils.len()) {
+            Some(end) if end <= self.quantity => {}
+            _ => return Err(Error::BufferSize),
+        }
+        for (i, coil) in coils.iter().enumerate() {
+            self.set(start + i, *coil)?;
+        }
+        Ok(())
+    }
+    /// Borrow the coils as read-only view.
+    pub fn as_coils(&self) -> Coils<'_> {
+        Coils {
+            data: self.data,
+            quantity: self.quantity,
+        }
+    }
+    /// Turn the coils into a read-only view of the buffer.
+    pub fn into_coils(self) -> Coils<'c> {
+        Coils {
+            data: self.data,
+            quantity: self.quantity,
+        }
+    }
+}
+
 /// Turn a bool into a u16 coil value
 pub fn bool_to_u16_coil(state: bool) -> u16 {
     if state {
@@ -96,7 +195,7 @@ pub fn u16_coil_to_bool(coil: u16) -> Result<bool, Error> {
 
 /// Calculate the number of bytes required for a given number of coils.
 pub const fn packed_coils_len(bitcount: usize) -> usize {
-    (bitcount + 7) / 8
+    bitcount.div_ceil(8)
 }
 
 ///  Pack coils into a byte array.
@@ -107,9 +206,10 @@ pub fn pack_coils(coils: &[Coil], bytes: &mut [u8]) -> Result<usize, Error> {
     if bytes.len() < packed_size {
         return Err(Error::BufferSize);
     }
+    bytes[..packed_size].fill(0);
     coils.iter().enumerate().for_each(|(i, b)| {
         let v = if *b { 0b1 } else { 0b0 };
-        bytes[(i / 8) as usize] |= v << (i % 8);
+        bytes[i / 8] |= v << (i % 8);
     });
     Ok(packed_size)
 }
@@ -144,6 +244,60 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn from_bools_into_used_buffer() {
+        let buff: &mut [u8] = &mut [0xFF, 0xFF];
+        let coils = Coils::from_bools(&[false, true], buff).unwrap();
+        assert_eq!(coils.data, &[0b10]);
+        assert_eq!(coils.packed_len(), 1);
+    }
+
+    #[test]
+    fn new_from_packed_bytes() {
+        let coils = Coils::new(&[0b1001], 4).unwrap();
+        assert_eq!(coils.get(3), Some(true));
+        assert_eq!(Coils::new(&[0b1001], 9), Err(Error::BufferSize));
+        assert_eq!(Coils::new(&[0, 0], 8), Err(Error::BufferSize));
+        assert!(Coils::new(&[], 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn build_coils_in_place() {
+        let buf = &mut [0xFF; 3];
+        assert_eq!(CoilsMut::new(buf, 25).err(), Some(Error::BufferSize));
+        let mut coils = CoilsMut::new(buf, 10).unwrap();
+        assert_eq!(coils.len(), 10);
+        assert_eq!(coils.get(9), Some(false));
+        coils.set(0, true).unwrap();
+        coils.set(9, true).unwrap();
+        assert_eq!(coils.set(10, true), Err(Error::BufferSize));
+        coils.set_range(2, &[true, true, false]).unwrap();
+        assert_eq!(coils.set_range(8, &[true; 3]), Err(Error::BufferSize));
+        assert_eq!(coils.set_range(usize::MAX, &[true]), Err(Error::BufferSize));
+        assert_eq!(coils.get(3), Some(true));
+        let coils = coils.into_coils();
+        assert_eq!(coils, Coils::new(&[0b0000_1101, 0b10], 10).unwrap());
+        assert_eq!(buf, &[0b0000_1101, 0b10, 0xFF]);
+    }
+
+    #[test]
+    fn fill_and_copy_coils() {
+        let buf = &mut [0; 2];
+        let mut coils = CoilsMut::new(buf, 10).unwrap();
+        coils.fill(true);
+        assert_eq!(coils.as_coils().into_iter().filter(|c| *c).count(), 10);
+        assert_eq!(coils.into_coils().data, &[0xFF, 0b11]);
+
+        let mut coils = CoilsMut::new(buf, 3).unwrap();
+        let src = Coils::new(&[0b101], 3).unwrap();
+        coils.copy_from(src).unwrap();
+        assert_eq!(coils.as_coils(), src);
+        let src = Coils::new(&[0b101], 4).unwrap();
+        assert_eq!(coils.copy_from(src), Err(Error::BufferSize));
+        coils.fill(false);
+        assert_eq!(coils.get(0), Some(false));
+    }
+
     #[test]
     fn coils_len() {
         let coils = Coils {
@@ -237,6 +391,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn convert_coil_to_bool() {
         assert_eq!(u16_coil_to_bool(0xFF00).unwrap(), true);
         assert_eq!(u16_coil_to_bool(0x0000).unwrap(), false);
@@ -18,10 +18,19 @@ impl<'d> Data<'d> {
             BigEndian::write_u16(&mut target[i * 2..], *w);
         }
         Ok(Data {
-            data: target,
+            data: &target[..words.len() * 2],
             quantity: words.len(),
         })
     }
+    /// Use already encoded words.
+    ///
+    /// The length of `data` has to be two bytes per word.
+    pub fn new(data: &'d [u8], quantity: usize) -> Result<Self, Error> {
+        if data.len() != quantity * 2 {
+            return Err(Error::BufferSize);
+        }
+        Ok(Data { data, quantity })
+    }
     //TODO: add tests
     pub(crate) fn copy_to(&self, buf: &mut [u8]) {
         let cnt = self.quantity * 2;
@@ -75,6 +84,84 @@ impl<'d> IntoIterator for Data<'d> {
     }
 }
 
+/// Mutable Modbus data (u16 values) in a caller provided buffer.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DataMut<'d> {
+    pub(crate) data: &'d mut [u8],
+    pub(crate) quantity: usize,
+}
+
+impl<'d> DataMut<'d> {
+    /// Use a byte buffer to hold `quantity` words that are initially zero.
+    pub fn new(target: &'d mut [u8], quantity: usize) -> Result<Self, Error> {
+        if target.len() < quantity * 2 {
+            return Err(Error::BufferSize);
+        }
+        let data = &mut target[..quantity * 2];
+        data.fill(0);
+        Ok(DataMut { data, quantity })
+    }
+    /// Quantity of words (u16 values)
+    pub const fn len(&self) -> usize {
+        self.quantity
+    }
+    ///  Returns `true` if the container has no items.
+    pub const fn is_empty(&self) -> bool {
+        self.quantity == 0
+    }
+    /// Get a specific word.
+    pub fn get(&self, idx: usize) -> Option<Word> {
+        self.as_data().get(idx)
+    }
+    /// Set a specific word.
+    pub fn set(&mut self, idx: usize, word: Word) -> Result<(), Error> {
+        if idx >= self.quantity {
+            return Err(Error::BufferSize);
+        }
+        BigEndian::write_u16(&mut self.data[idx * 2..], word);
+        Ok(())
+    }
+    /// Set all words to the same value.
+    pub fn fill(&mut self, word: Word) {
+        self.data
+            .chunks_exact_mut(2)
+            .for_each(|w| BigEndian::write_u16(w, word));
+    }
+    /// Copy all words from `src` that has to be of the same length.
+    pub fn copy_from(&mut self, src: Data) -> Result<(), Error> {
+        if src.len() != self.quantity {
+            return Err(Error::BufferSize);
+        }
+        self.data.copy_from_slice(&src.data[..self.data.len()]);
+        Ok(())
+    }
+    /// Set consecutive words beginning with the one at index `start`.
+    pub fn set_range(&mut self, start: usize, words: &[Word]) -> Result<(), Error> {
+        match start.checked_add(words.len()) {
+            Some(end) if end <= self.quantity => {}
+            _ => return Err(Error::BufferSize),
+        }
+        for (i, word) in words.iter().enumerate() {
+            self.set(start + i, *word)?;
+        }
+        Ok(())
+    }
+    /// Borrow the words as read-only view.
+    pub fn as_data(&self) -> Data<'_> {
+        Data {
+            data: self.data,
+            quantity: self.quantity,
+        }
+    }
+    /// Turn the words into a read-only view of the buffer.
+    pub fn into_data(self) -> Data<'d> {
+        Data {
+            data: self.data,
+            quantity: self.quantity,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -95,6 +182,55 @@ mod tests {
         assert_eq!(iter.next(), None);
     }
 
+    #[test]
+    fn from_words_into_larger_buffer() {
+        let buff: &mut [u8] = &mut [0; 6];
+        let data = Data::from_words(&[0xABCD], buff).unwrap();
+        assert_eq!(data.data, &[0xAB, 0xCD]);
+    }
+
+    #[test]
+    fn new_from_bytes() {
+        let data = Data::new(&[0x12, 0x34], 1).unwrap();
+        assert_eq!(data.get(0), Some(0x1234));
+        assert_eq!(Data::new(&[0x12, 0x34], 2), Err(Error::BufferSize));
+        assert_eq!(Data::new(&[0x12, 0x34, 0x56], 1), Err(Error::BufferSize));
+    }
+
+    #[test]
+    fn build_data_in_place() {
+        let buf = &mut [0xFF; 7];
+        assert_eq!(DataMut::new(buf, 4).err(), Some(Error::BufferSize));
+        let mut data = DataMut::new(buf, 3).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data.get(2), Some(0));
+        data.set(0, 0xABCD).unwrap();
+        assert_eq!(data.set(3, 1), Err(Error::BufferSize));
+        data.set_range(1, &[0x1234, 0x5678]).unwrap();
+        assert_eq!(data.set_range(2, &[1, 2]), Err(Error::BufferSize));
+        assert_eq!(data.set_range(usize::MAX, &[1]), Err(Error::BufferSize));
+        let data = data.into_data();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data.get(1), Some(0x1234));
+        assert_eq!(buf, &[0xAB, 0xCD, 0x12, 0x34, 0x56, 0x78, 0xFF]);
+    }
+
+    #[test]
+    fn fill_and_copy_data() {
+        let buf = &mut [0; 4];
+        let mut data = DataMut::new(buf, 2).unwrap();
+        data.fill(0x0102);
+        assert_eq!(
+            data.as_data().into_iter().filter(|w| *w == 0x0102).count(),
+            2
+        );
+        let src = Data::new(&[0xAB, 0xCD, 0xEF, 0x12], 2).unwrap();
+        data.copy_from(src).unwrap();
+        assert_eq!(data.as_data(), src);
+        let src = Data::new(&[0xAB, 0xCD], 1).unwrap();
+        assert_eq!(data.copy_from(src), Err(Error::BufferSize));
+    }
+
     #[test]
     fn data_len() {
         let data = Data {
@@ -0,0 +1,226 @@
+use super::*;
+
+// [MODBUS Application Protocol Specification V1.1b3](http://modbus.org/docs/Modbus_Application_Protocol_V1_1b3.pdf), page 43
+// "The total length of the response data must be less than 253 bytes"
+// therefore the objects may use the PDU size minus the 7 bytes header.
+pub(crate) const MAX_OBJECTS_LEN: usize = 253 - 7;
+
+/// Identifies a single device identification object.
+///
+/// - `0x00` - `0x02`: basic objects (vendor name, product code, revision)
+/// - `0x03` - `0x06`: regular objects
+/// - `0x80` - `0xFF`: private (extended) objects
+pub type ObjectId = u8;
+
+/// The conformity level of a device.
+pub type ConformityLevel = u8;
+
+/// The category of device identification objects to read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadDeviceIdCode {
+    /// Request to get the basic device identification (stream access)
+    Basic = 0x01,
+    /// Request to get the regular device identification (stream access)
+    Regular = 0x02,
+    /// Request to get the extended device identification (stream access)
+    Extended = 0x03,
+    /// Request to get one specific identification object (individual access)
+    Specific = 0x04,
+}
+
+/// The device identification objects of a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceIdentification<'r> {
+    pub read_device_id_code: ReadDeviceIdCode,
+    pub conformity_level: ConformityLevel,
+    /// `true` if the objects do not fit into a single response.
+    pub more_follows: bool,
+    /// The object to start the next request with if `more_follows` is set.
+    pub next_object_id: ObjectId,
+    pub(crate) number_of_objects: u8,
+    pub(crate) objects: RawData<'r>,
+}
+
+impl<'r> DeviceIdentification<'r> {
+    /// Number of objects
+    pub const fn len(&self) -> usize {
+        self.number_of_objects as usize
+    }
+    ///  Returns `true` if the container has no items.
+    pub const fn is_empty(&self) -> bool {
+        self.number_of_objects == 0
+    }
+    /// Iterate over all `(object_id, value)` pairs.
+    pub const fn objects(&self) -> DeviceIdObjectsIter<'r> {
+        DeviceIdObjectsIter {
+            cnt: self.number_of_objects,
+            data: self.objects,
+        }
+    }
+    /// Get the value of a specific object.
+    pub fn get(&self, id: ObjectId) -> Option<&'r [u8]> {
+        self.objects()
+            .find(|(object_id, _)| *object_id == id)
+            .map(|(_, value)| value)
+    }
+}
+
+/// Device identification object iterator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeviceIdObjectsIter<'r> {
+    cnt: u8,
+    data: RawData<'r>,
+}
+
+impl<'r> Iterator for DeviceIdObjectsIter<'r> {
+    type Item = (ObjectId, &'r [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cnt == 0 || self.data.len() < 2 {
+            return None;
+        }
+        let id = self.data[0];
+        let len = self.data[1] as usize;
+        if self.data.len() < 2 + len {
+            return None;
+        }
+        let (object, rest) = self.data.split_at(2 + len);
+        self.data = rest;
+        self.cnt -= 1;
+        Some((id, &object[2..]))
+    }
+}
+
+/// Build a device identification response in a byte buffer.
+///
+/// Objects that do not fit into the buffer (or the PDU) are not added,
+/// instead `more_follows` is set and `next_object_id` points to the
+/// first object that has been left out.
+#[derive(Debug)]
+pub struct DeviceIdentificationBuilder<'b> {
+    read_device_id_code: ReadDeviceIdCode,
+    conformity_level: ConformityLevel,
+    next_object_id: Option<ObjectId>,
+    number_of_objects: u8,
+    len: usize,
+    target: &'b mut [u8],
+}
+
+impl<'b> DeviceIdentificationBuilder<'b> {
+    /// Create a new builder that writes the objects into `target`.
+    pub fn new(
+        read_device_id_code: ReadDeviceIdCode,
+        conformity_level: ConformityLevel,
+        target: &'b mut [u8],
+    ) -> Self {
+        Self {
+            read_device_id_code,
+            conformity_level,
+            next_object_id: None,
+            number_of_objects: 0,
+            len: 0,
+            target,
+        }
+    }
+    /// Add an object.
+    ///
+    /// It returns `false` if the object did not fit.
+    pub fn push(&mut self, id: ObjectId, value: &[u8]) -> bool {
+        if self.next_object_id.is_some() {
+            return false;
+        }
+        let end = self.len + 2 + value.len();
+        if value.len() > u8::MAX as usize || end > self.target.len() || end > MAX_OBJECTS_LEN {
+            self.next_object_id = Some(id);
+            return false;
+        }
+        self.target[self.len] = id;
+        self.target[self.len + 1] = value.len() as u8;
+        self.target[self.len + 2..end].copy_from_slice(value);
+        self.len = end;
+        self.number_of_objects += 1;
+        true
+    }
+    /// Finish the response.
+    pub fn build(self) -> DeviceIdentification<'b> {
+        DeviceIdentification {
+            read_device_id_code: self.read_device_id_code,
+            conformity_level: self.conformity_level,
+            more_follows: self.next_object_id.is_some(),
+            next_object_id: self.next_object_id.unwrap_or(0),
+            number_of_objects: self.number_of_objects,
+            objects: &self.target[..self.len],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn iterate_over_objects() {
+        let dev_id = DeviceIdentification {
+            read_device_id_code: ReadDeviceIdCode::Basic,
+            conformity_level: 0x01,
+            more_follows: false,
+            next_object_id: 0,
+            number_of_objects: 2,
+            objects: &[0x00, 0x03, b'F', b'o', b'o', 0x01, 0x02, b'4', b'2'],
+        };
+        assert_eq!(dev_id.len(), 2);
+        let mut iter = dev_id.objects();
+        assert_eq!(iter.next(), Some((0x00, &b"Foo"[..])));
+        assert_eq!(iter.next(), Some((0x01, &b"42"[..])));
+        assert_eq!(iter.next(), None);
+        assert_eq!(dev_id.get(0x01), Some(&b"42"[..]));
+        assert_eq!(dev_id.get(0x02), None);
+    }
+
+    #[test]
+    fn iterate_over_truncated_objects() {
+        let dev_id = DeviceIdentification {
+            read_device_id_code: ReadDeviceIdCode::Basic,
+            conformity_level: 0x01,
+            more_follows: false,
+            next_object_id: 0,
+            number_of_objects: 2,
+            objects: &[0x00, 0x03, b'F', b'o', b'o', 0x01, 0x02, b'4'],
+        };
+        let mut iter = dev_id.objects();
+        assert!(iter.next().is_some());
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn build_device_identification() {
+        let buf = &mut [0; 12];
+        let mut builder = DeviceIdentificationBuilder::new(ReadDeviceIdCode::Basic, 0x81, buf);
+        assert!(builder.push(0x00, b"Foo"));
+        assert!(builder.push(0x01, b"Bar"));
+        assert!(!builder.push(0x02, b"v1.0"));
+        assert!(!builder.push(0x03, b""));
+        let dev_id = builder.build();
+        assert_eq!(dev_id.read_device_id_code, ReadDeviceIdCode::Basic);
+        assert_eq!(dev_id.conformity_level, 0x81);
+        assert!(dev_id.more_follows);
+        assert_eq!(dev_id.next_object_id, 0x02);
+        assert_eq!(dev_id.len(), 2);
+        assert_eq!(
+            dev_id.objects,
+            &[0x00, 0x03, b'F', b'o', b'o', 0x01, 0x03, b'B', b'a', b'r']
+        );
+    }
+
+    #[test]
+    fn build_complete_device_identification() {
+        let buf = &mut [0; 20];
+        let mut builder = DeviceIdentificationBuilder::new(ReadDeviceIdCode::Specific, 0x01, buf);
+        assert!(builder.push(0x02, b"v1.0"));
+        let dev_id = builder.build();
+        assert!(!dev_id.more_follows);
+        assert_eq!(dev_id.next_object_id, 0x00);
+        assert_eq!(dev_id.get(0x02), Some(&b"v1.0"[..]));
+    }
+}
@@ -0,0 +1,276 @@
+use super::*;
+use crate::error::*;
+
+/// A diagnostics (`0x08`) sub-function.
+///
+/// It is represented by an unsigned 16 bit integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticSubFunction {
+    ReturnQueryData,
+    RestartCommunicationsOption,
+    ReturnDiagnosticRegister,
+    ChangeAsciiInputDelimiter,
+    ForceListenOnlyMode,
+    ClearCountersAndDiagnosticRegister,
+    ReturnBusMessageCount,
+    ReturnBusCommunicationErrorCount,
+    ReturnBusExceptionErrorCount,
+    ReturnServerMessageCount,
+    ReturnServerNoResponseCount,
+    ReturnServerNakCount,
+    ReturnServerBusyCount,
+    ReturnBusCharacterOverrunCount,
+    ClearOverrunCounterAndFlag,
+    Custom(u16),
+}
+
+impl From<u16> for DiagnosticSubFunction {
+    fn from(c: u16) -> Self {
+        use DiagnosticSubFunction::*;
+
+        match c {
+            0x00 => ReturnQueryData,
+            0x01 => RestartCommunicationsOption,
+            0x02 => ReturnDiagnosticRegister,
+            0x03 => ChangeAsciiInputDelimiter,
+            0x04 => ForceListenOnlyMode,
+            0x0A => ClearCountersAndDiagnosticRegister,
+            0x0B => ReturnBusMessageCount,
+            0x0C => ReturnBusCommunicationErrorCount,
+            0x0D => ReturnBusExceptionErrorCount,
+            0x0E => ReturnServerMessageCount,
+            0x0F => ReturnServerNoResponseCount,
+            0x10 => ReturnServerNakCount,
+            0x11 => ReturnServerBusyCount,
+            0x12 => ReturnBusCharacterOverrunCount,
+            0x14 => ClearOverrunCounterAndFlag,
+            _ => Custom(c),
+        }
+    }
+}
+
+impl From<DiagnosticSubFunction> for u16 {
+    fn from(sub_fn: DiagnosticSubFunction) -> u16 {
+        use DiagnosticSubFunction::*;
+
+        match sub_fn {
+            ReturnQueryData => 0x00,
+            RestartCommunicationsOption => 0x01,
+            ReturnDiagnosticRegister => 0x02,
+            ChangeAsciiInputDelimiter => 0x03,
+            ForceListenOnlyMode => 0x04,
+            ClearCountersAndDiagnosticRegister => 0x0A,
+            ReturnBusMessageCount => 0x0B,
+            ReturnBusCommunicationErrorCount => 0x0C,
+            ReturnBusExceptionErrorCount => 0x0D,
+            ReturnServerMessageCount => 0x0E,
+            ReturnServerNoResponseCount => 0x0F,
+            ReturnServerNakCount => 0x10,
+            ReturnServerBusyCount => 0x11,
+            ReturnBusCharacterOverrunCount => 0x12,
+            ClearOverrunCounterAndFlag => 0x14,
+            Custom(c) => c,
+        }
+    }
+}
+
+/// The interpreted data of a diagnostics response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticsData<'r> {
+    /// The echoed query data.
+    QueryData(Data<'r>),
+    /// The communications restarted; `true` if the event log has been cleared.
+    RestartCommunications(bool),
+    /// The content of the diagnostic register.
+    DiagnosticRegister(Word),
+    /// The new ASCII input delimiter.
+    AsciiInputDelimiter(u8),
+    /// The counters have been cleared.
+    Cleared,
+    /// The value of the requested counter.
+    Counter(Word),
+    /// The raw data of an unknown sub-function.
+    Custom(Data<'r>),
+}
+
+impl DiagnosticSubFunction {
+    /// Validate the data field of a diagnostics request.
+    pub fn validate_request(self, data: &Data) -> Result<(), Error> {
+        use DiagnosticSubFunction::*;
+
+        match self {
+            ReturnQueryData | Custom(_) => Ok(()),
+            RestartCommunicationsOption => match single_word(data)? {
+                0x0000 | 0xFF00 => Ok(()),
+                word => Err(Error::DiagnosticsData(word)),
+            },
+            ChangeAsciiInputDelimiter => match single_word(data)? {
+                word if word & 0x00FF == 0 => Ok(()),
+                word => Err(Error::DiagnosticsData(word)),
+            },
+            _ => match single_word(data)? {
+                0x0000 => Ok(()),
+                word => Err(Error::DiagnosticsData(word)),
+            },
+        }
+    }
+
+    /// Interpret the data field of a diagnostics response.
+    pub fn interpret_response<'r>(self, data: Data<'r>) -> Result<DiagnosticsData<'r>, Error> {
+        use DiagnosticSubFunction::*;
+
+        let interpreted = match self {
+            ReturnQueryData => DiagnosticsData::QueryData(data),
+            RestartCommunicationsOption => match single_word(&data)? {
+                0x0000 => DiagnosticsData::RestartCommunications(false),
+                0xFF00 => DiagnosticsData::RestartCommunications(true),
+                word => return Err(Error::DiagnosticsData(word)),
+            },
+            ReturnDiagnosticRegister => DiagnosticsData::DiagnosticRegister(single_word(&data)?),
+            ChangeAsciiInputDelimiter => {
+                DiagnosticsData::AsciiInputDelimiter((single_word(&data)? >> 8) as u8)
+            }
+            // A server in listen only mode does not respond.
+            ForceListenOnlyMode => return Err(Error::SubFnCode(self.into())),
+            ClearCountersAndDiagnosticRegister | ClearOverrunCounterAndFlag => {
+                single_word(&data)?;
+                DiagnosticsData::Cleared
+            }
+            ReturnBusMessageCount
+            | ReturnBusCommunicationErrorCount
+            | ReturnBusExceptionErrorCount
+            | ReturnServerMessageCount
+            | ReturnServerNoResponseCount
+            | ReturnServerNakCount
+            | ReturnServerBusyCount
+            | ReturnBusCharacterOverrunCount => DiagnosticsData::Counter(single_word(&data)?),
+            Custom(_) => DiagnosticsData::Custom(data),
+        };
+        Ok(interpreted)
+    }
+}
+
+fn single_word(data: &Data) -> Result<Word, Error> {
+    if data.len() != 1 {
+        return Err(Error::ByteCount((data.len() * 2) as u8));
+    }
+    data.get(0).ok_or(Error::BufferSize)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn sub_function_into_u16() {
+        let x: u16 = DiagnosticSubFunction::ReturnServerBusyCount.into();
+        assert_eq!(x, 0x11);
+        let x: u16 = DiagnosticSubFunction::Custom(0xBB).into();
+        assert_eq!(x, 0xBB);
+    }
+
+    #[test]
+    fn sub_function_from_u16() {
+        assert_eq!(
+            DiagnosticSubFunction::from(0x0C),
+            DiagnosticSubFunction::ReturnBusCommunicationErrorCount
+        );
+        assert_eq!(
+            DiagnosticSubFunction::from(0x13),
+            DiagnosticSubFunction::Custom(0x13)
+        );
+        for c in 0..0x20 {
+            assert_eq!(u16::from(DiagnosticSubFunction::from(c)), c);
+        }
+    }
+
+    #[test]
+    fn validate_request_data() {
+        use DiagnosticSubFunction::*;
+
+        let buf = &mut [0; 4];
+        let data = Data::from_words(&[0xA537, 0x1234], buf).unwrap();
+        assert!(ReturnQueryData.validate_request(&data).is_ok());
+        assert_eq!(
+            ReturnBusMessageCount.validate_request(&data).err().unwrap(),
+            Error::ByteCount(4)
+        );
+
+        let buf = &mut [0; 2];
+        let data = Data::from_words(&[0xFF00], buf).unwrap();
+        assert!(RestartCommunicationsOption.validate_request(&data).is_ok());
+        assert_eq!(
+            ClearCountersAndDiagnosticRegister
+                .validate_request(&data)
+                .err()
+                .unwrap(),
+            Error::DiagnosticsData(0xFF00)
+        );
+
+        let buf = &mut [0; 2];
+        let data = Data::from_words(&[0x0A00], buf).unwrap();
+        assert!(ChangeAsciiInputDelimiter.validate_request(&data).is_ok());
+
+        let buf = &mut [0; 2];
+        let data = Data::from_words(&[0x0A0D], buf).unwrap();
+        assert_eq!(
+            ChangeAsciiInputDelimiter
+                .validate_request(&data)
+                .err()
+                .unwrap(),
+            Error::DiagnosticsData(0x0A0D)
+        );
+
+        let buf = &mut [0; 2];
+        let data = Data::from_words(&[0x0000], buf).unwrap();
+        assert!(ReturnServerNakCount.validate_request(&data).is_ok());
+        assert!(ForceListenOnlyMode.validate_request(&data).is_ok());
+    }
+
+    #[test]
+    fn interpret_response_data() {
+        use DiagnosticSubFunction::*;
+
+        let buf = &mut [0; 2];
+        let data = Data::from_words(&[0x0108], buf).unwrap();
+        assert_eq!(
+            ReturnBusCommunicationErrorCount
+                .interpret_response(data)
+                .unwrap(),
+            DiagnosticsData::Counter(0x0108)
+        );
+        assert_eq!(
+            ReturnDiagnosticRegister.interpret_response(data).unwrap(),
+            DiagnosticsData::DiagnosticRegister(0x0108)
+        );
+        assert_eq!(
+            ReturnQueryData.interpret_response(data).unwrap(),
+            DiagnosticsData::QueryData(data)
+        );
+        assert_eq!(
+            ChangeAsciiInputDelimiter.interpret_response(data).unwrap(),
+            DiagnosticsData::AsciiInputDelimiter(0x01)
+        );
+        assert_eq!(
+            ForceListenOnlyMode.interpret_response(data).err().unwrap(),
+            Error::SubFnCode(0x04)
+        );
+
+        let buf = &mut [0; 2];
+        let data = Data::from_words(&[0xFF00], buf).unwrap();
+        assert_eq!(
+            RestartCommunicationsOption
+                .interpret_response(data)
+                .unwrap(),
+            DiagnosticsData::RestartCommunications(true)
+        );
+
+        let buf = &mut [0; 2];
+        let data = Data::from_words(&[0x0000], buf).unwrap();
+        assert_eq!(
+            ClearOverrunCounterAndFlag.interpret_response(data).unwrap(),
+            DiagnosticsData::Cleared
+        );
+    }
+}
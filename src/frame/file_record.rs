@@ -0,0 +1,351 @@
+use super::*;
+use crate::error::*;
+
+/// The only reference type defined for file records.
+pub(crate) const FILE_REFERENCE_TYPE: u8 = 0x06;
+
+/// Maximum number of bytes all sub-requests or sub-responses may occupy.
+// [MODBUS Application Protocol Specification V1.1b3](http://modbus.org/docs/Modbus_Application_Protocol_V1_1b3.pdf), page 32
+const MAX_FILE_RECORDS_LEN: usize = 0xFB;
+
+/// Size of a single read file record sub-request.
+const SUB_REQUEST_LEN: usize = 7;
+
+/// A file number (`1` to `65535`).
+pub(crate) type FileNumber = u16;
+
+/// A record number within a file (`0` to `9999`).
+pub(crate) type RecordNumber = u16;
+
+/// A sub-request of a read file record request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileSubRequest {
+    pub file: FileNumber,
+    pub record: RecordNumber,
+    /// Number of registers to read
+    pub length: Quantity,
+}
+
+/// A group of registers within a file.
+///
+/// It's used for write file record sub-requests and responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileRecord<'r> {
+    pub file: FileNumber,
+    pub record: RecordNumber,
+    pub data: Data<'r>,
+}
+
+/// Packed read file record sub-requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileSubRequests<'r> {
+    pub(crate) data: RawData<'r>,
+}
+
+/// Packed read file record sub-responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileSubResponses<'r> {
+    pub(crate) data: RawData<'r>,
+}
+
+/// Packed file records of a write file record request or response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileRecords<'r> {
+    pub(crate) data: RawData<'r>,
+}
+
+impl<'r> FileSubRequests<'r> {
+    /// Pack sub-requests into a byte buffer.
+    pub fn from_sub_requests(
+        sub_requests: &[FileSubRequest],
+        target: &'r mut [u8],
+    ) -> Result<Self, Error> {
+        let len = sub_requests.len() * SUB_REQUEST_LEN;
+        if len > target.len() || len > MAX_FILE_RECORDS_LEN {
+            return Err(Error::BufferSize);
+        }
+        for (i, req) in sub_requests.iter().enumerate() {
+            let buf = &mut target[i * SUB_REQUEST_LEN..];
+            buf[0] = FILE_REFERENCE_TYPE;
+            BigEndian::write_u16(&mut buf[1..], req.file);
+            BigEndian::write_u16(&mut buf[3..], req.record);
+            BigEndian::write_u16(&mut buf[5..], req.length);
+        }
+        Ok(FileSubRequests {
+            data: &target[..len],
+        })
+    }
+    /// Number of sub-requests
+    pub const fn len(&self) -> usize {
+        self.data.len() / SUB_REQUEST_LEN
+    }
+    ///  Returns `true` if the container has no items.
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Get a specific sub-request.
+    pub fn get(&self, idx: usize) -> Option<FileSubRequest> {
+        if idx + 1 > self.len() {
+            return None;
+        }
+        let buf = &self.data[idx * SUB_REQUEST_LEN..];
+        Some(FileSubRequest {
+            file: BigEndian::read_u16(&buf[1..]),
+            record: BigEndian::read_u16(&buf[3..]),
+            length: BigEndian::read_u16(&buf[5..]),
+        })
+    }
+}
+
+impl<'r> FileSubResponses<'r> {
+    /// Pack the record data of the sub-responses into a byte buffer.
+    pub fn from_records(records: &[Data], target: &'r mut [u8]) -> Result<Self, Error> {
+        let len = records.iter().map(|r| 2 + r.len() * 2).sum();
+        if len > target.len() || len > MAX_FILE_RECORDS_LEN {
+            return Err(Error::BufferSize);
+        }
+        let mut pos = 0;
+        for r in records {
+            target[pos] = (1 + r.len() * 2) as u8;
+            target[pos + 1] = FILE_REFERENCE_TYPE;
+            r.copy_to(&mut target[pos + 2..]);
+            pos += 2 + r.len() * 2;
+        }
+        Ok(FileSubResponses {
+            data: &target[..len],
+        })
+    }
+    /// Number of sub-responses
+    pub fn len(&self) -> usize {
+        self.into_iter().count()
+    }
+    ///  Returns `true` if the container has no items.
+    pub const fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl<'r> FileRecords<'r> {
+    /// Pack file records into a byte buffer.
+    pub fn from_records(records: &[FileRecord], target: &'r mut [u8]) -> Result<Self, Error> {
+        let len = records.iter().map(|r| 7 + r.data.len() * 2).sum();
+        if len > target.len() || len > MAX_FILE_RECORDS_LEN {
+            return Err(Error::BufferSize);
+        }
+        let mut pos = 0;
+        for r in records {
+            let buf = &mut target[pos..];
+            buf[0] = FILE_REFERENCE_TYPE;
+            BigEndian::write_u16(&mut buf[1..], r.file);
+            BigEndian::write_u16(&mut buf[3..], r.record);
+            BigEndian::write_u16(&mut buf[5..], r.data.len() as u16);
+            r.data.copy_to(&mut buf[7..]);
+            pos += 7 + r.data.len() * 2;
+        }
+        Ok(FileRecords {
+            data: &target[..len],
+        })
+    }
+    /// Number of file records
+    pub fn len(&self) -> usize {
+        self.into_iter().count()
+    }
+    ///  Returns `true` if the container has no items.
+    pub const fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+/// Read file record sub-request iterator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileSubRequestsIter<'r> {
+    cnt: usize,
+    sub_requests: FileSubRequests<'r>,
+}
+
+impl<'r> Iterator for FileSubRequestsIter<'r> {
+    type Item = FileSubRequest;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.sub_requests.get(self.cnt);
+        self.cnt += 1;
+        result
+    }
+}
+
+impl<'r> IntoIterator for FileSubRequests<'r> {
+    type Item = FileSubRequest;
+    type IntoIter = FileSubRequestsIter<'r>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FileSubRequestsIter {
+            cnt: 0,
+            sub_requests: self,
+        }
+    }
+}
+
+/// Read file record sub-response iterator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileSubResponsesIter<'r> {
+    data: RawData<'r>,
+}
+
+impl<'r> Iterator for FileSubResponsesIter<'r> {
+    type Item = Data<'r>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.len() < 2 {
+            return None;
+        }
+        let len = 1 + self.data[0] as usize;
+        if len < 2 || len > self.data.len() {
+            return None;
+        }
+        let (sub_rsp, rest) = self.data.split_at(len);
+        self.data = rest;
+        let quantity = (len - 2) / 2;
+        Some(Data {
+            quantity,
+            data: &sub_rsp[2..2 + quantity * 2],
+        })
+    }
+}
+
+impl<'r> IntoIterator for FileSubResponses<'r> {
+    type Item = Data<'r>;
+    type IntoIter = FileSubResponsesIter<'r>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FileSubResponsesIter { data: self.data }
+    }
+}
+
+/// File record iterator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileRecordsIter<'r> {
+    data: RawData<'r>,
+}
+
+impl<'r> Iterator for FileRecordsIter<'r> {
+    type Item = FileRecord<'r>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.len() < 7 {
+            return None;
+        }
+        let quantity = BigEndian::read_u16(&self.data[5..]) as usize;
+        let len = 7 + quantity * 2;
+        if len > self.data.len() {
+            return None;
+        }
+        let (record, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(FileRecord {
+            file: BigEndian::read_u16(&record[1..]),
+            record: BigEndian::read_u16(&record[3..]),
+            data: Data {
+                quantity,
+                data: &record[7..],
+            },
+        })
+    }
+}
+
+impl<'r> IntoIterator for FileRecords<'r> {
+    type Item = FileRecord<'r>;
+    type IntoIter = FileRecordsIter<'r>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        FileRecordsIter { data: self.data }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn from_sub_request_slice() {
+        let reqs = &[
+            FileSubRequest {
+                file: 4,
+                record: 1,
+                length: 2,
+            },
+            FileSubRequest {
+                file: 3,
+                record: 9,
+                length: 2,
+            },
+        ];
+        let buff: &mut [u8] = &mut [0; 13];
+        assert!(FileSubRequests::from_sub_requests(reqs, buff).is_err());
+        let buff: &mut [u8] = &mut [0; 14];
+        let sub_requests = FileSubRequests::from_sub_requests(reqs, buff).unwrap();
+        assert_eq!(
+            sub_requests.data,
+            &[0x06, 0x00, 0x04, 0x00, 0x01, 0x00, 0x02, 0x06, 0x00, 0x03, 0x00, 0x09, 0x00, 0x02]
+        );
+        assert_eq!(sub_requests.len(), 2);
+        let mut iter = sub_requests.into_iter();
+        assert_eq!(iter.next(), Some(reqs[0]));
+        assert_eq!(iter.next(), Some(reqs[1]));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn from_sub_response_records() {
+        let buff: &mut [u8] = &mut [0; 4];
+        let a = Data::from_words(&[0x0DFE, 0x0020], buff).unwrap();
+        let buff: &mut [u8] = &mut [0; 4];
+        let b = Data::from_words(&[0x33CD, 0x0040], buff).unwrap();
+        let buff: &mut [u8] = &mut [0; 11];
+        assert!(FileSubResponses::from_records(&[a, b], buff).is_err());
+        let buff: &mut [u8] = &mut [0; 12];
+        let sub_responses = FileSubResponses::from_records(&[a, b], buff).unwrap();
+        assert_eq!(
+            sub_responses.data,
+            &[0x05, 0x06, 0x0D, 0xFE, 0x00, 0x20, 0x05, 0x06, 0x33, 0xCD, 0x00, 0x40]
+        );
+        assert_eq!(sub_responses.len(), 2);
+        let mut iter = sub_responses.into_iter();
+        assert_eq!(iter.next(), Some(a));
+        assert_eq!(iter.next(), Some(b));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn from_file_records() {
+        let buff: &mut [u8] = &mut [0; 6];
+        let data = Data::from_words(&[0x06AF, 0x04BE, 0x100D], buff).unwrap();
+        let record = FileRecord {
+            file: 4,
+            record: 7,
+            data,
+        };
+        let buff: &mut [u8] = &mut [0; 12];
+        assert!(FileRecords::from_records(&[record], buff).is_err());
+        let buff: &mut [u8] = &mut [0; 13];
+        let records = FileRecords::from_records(&[record], buff).unwrap();
+        assert_eq!(
+            records.data,
+            &[0x06, 0x00, 0x04, 0x00, 0x07, 0x00, 0x03, 0x06, 0xAF, 0x04, 0xBE, 0x10, 0x0D]
+        );
+        assert_eq!(records.len(), 1);
+        let mut iter = records.into_iter();
+        assert_eq!(iter.next(), Some(record));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn empty_file_records() {
+        let records = FileRecords { data: &[] };
+        assert!(records.is_empty());
+        assert_eq!(records.into_iter().next(), None);
+        let sub_requests = FileSubRequests { data: &[] };
+        assert!(sub_requests.is_empty());
+        assert_eq!(sub_requests.get(0), None);
+    }
+}
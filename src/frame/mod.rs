@@ -1,9 +1,16 @@
 mod coils;
 mod data;
+mod device_id;
+#[cfg(feature = "rtu")]
+mod diagnostics;
+mod file_record;
 pub(crate) mod rtu;
 pub(crate) mod tcp;
+mod value;
 
-pub use self::{coils::*, data::*};
+#[cfg(feature = "rtu")]
+pub use self::diagnostics::*;
+pub use self::{coils::*, data::*, device_id::*, file_record::*, value::*};
 use byteorder::{BigEndian, ByteOrder};
 use core::fmt;
 
@@ -21,6 +28,11 @@ pub enum FnCode {
     WriteSingleRegister,
     WriteMultipleRegisters,
     ReadWriteMultipleRegisters,
+    MaskWriteRegister,
+    ReadFifoQueue,
+    ReadFileRecord,
+    WriteFileRecord,
+    EncapsulatedInterfaceTransport,
     #[cfg(feature = "rtu")]
     ReadExceptionStatus,
     #[cfg(feature = "rtu")]
@@ -32,14 +44,7 @@ pub enum FnCode {
     #[cfg(feature = "rtu")]
     ReportServerId,
     //TODO:
-    //- ReadFileRecord
-    //- WriteFileRecord
-    //- MaskWriteRegiger
-    //TODO:
-    //- Read FifoQueue
-    //- EncapsulatedInterfaceTransport
     //- CanOpenGeneralReferenceRequestAndResponsePdu
-    //- ReadDeviceIdentification
     Custom(u8),
 }
 
@@ -57,6 +62,11 @@ impl From<u8> for FnCode {
             0x06 => WriteSingleRegister,
             0x10 => WriteMultipleRegisters,
             0x17 => ReadWriteMultipleRegisters,
+            0x16 => MaskWriteRegister,
+            0x18 => ReadFifoQueue,
+            0x14 => ReadFileRecord,
+            0x15 => WriteFileRecord,
+            0x2B => EncapsulatedInterfaceTransport,
             #[cfg(feature = "rtu")]
             0x07 => ReadExceptionStatus,
             #[cfg(feature = "rtu")]
@@ -72,11 +82,11 @@ impl From<u8> for FnCode {
     }
 }
 
-impl Into<u8> for FnCode {
-    fn into(self: Self) -> u8 {
+impl From<FnCode> for u8 {
+    fn from(code: FnCode) -> u8 {
         use FnCode::*;
 
-        match self {
+        match code {
             ReadCoils => 0x01,
             ReadDiscreteInputs => 0x02,
             WriteSingleCoil => 0x05,
@@ -86,6 +96,11 @@ impl Into<u8> for FnCode {
             WriteSingleRegister => 0x06,
             WriteMultipleRegisters => 0x10,
             ReadWriteMultipleRegisters => 0x17,
+            MaskWriteRegister => 0x16,
+            ReadFifoQueue => 0x18,
+            ReadFileRecord => 0x14,
+            WriteFileRecord => 0x15,
+            EncapsulatedInterfaceTransport => 0x2B,
             #[cfg(feature = "rtu")]
             ReadExceptionStatus => 0x07,
             #[cfg(feature = "rtu")]
@@ -101,8 +116,39 @@ impl Into<u8> for FnCode {
     }
 }
 
-/// A Modbus sub-function code is represented by an unsigned 16 bit integer.
-pub(crate) type SubFnCode = u16;
+/// A MEI (Modbus Encapsulated Interface) type.
+///
+/// It selects the interface of an encapsulated interface transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeiType {
+    CanOpenGeneralReference,
+    ReadDeviceIdentification,
+    Custom(u8),
+}
+
+impl From<u8> for MeiType {
+    fn from(t: u8) -> Self {
+        use MeiType::*;
+
+        match t {
+            0x0D => CanOpenGeneralReference,
+            0x0E => ReadDeviceIdentification,
+            _ => Custom(t),
+        }
+    }
+}
+
+impl From<MeiType> for u8 {
+    fn from(t: MeiType) -> u8 {
+        use MeiType::*;
+
+        match t {
+            CanOpenGeneralReference => 0x0D,
+            ReadDeviceIdentification => 0x0E,
+            Custom(t) => t,
+        }
+    }
+}
 
 /// A Modbus address is represented by 16 bit (from `0` to `65535`).
 pub(crate) type Address = u16;
@@ -119,6 +165,12 @@ pub(crate) type Word = u16;
 /// Number of items to process (`0` - `65535`).
 pub(crate) type Quantity = u16;
 
+/// AND mask of a masked register write.
+pub(crate) type AndMask = u16;
+
+/// OR mask of a masked register write.
+pub(crate) type OrMask = u16;
+
 /// Raw PDU data
 type RawData<'r> = &'r [u8];
 
@@ -134,10 +186,15 @@ pub enum Request<'r> {
     WriteSingleRegister(Address, Word),
     WriteMultipleRegisters(Address, Data<'r>),
     ReadWriteMultipleRegisters(Address, Quantity, Address, Data<'r>),
+    MaskWriteRegister(Address, AndMask, OrMask),
+    ReadFifoQueue(Address),
+    ReadFileRecord(FileSubRequests<'r>),
+    WriteFileRecord(FileRecords<'r>),
+    ReadDeviceIdentification(ReadDeviceIdCode, ObjectId),
     #[cfg(feature = "rtu")]
     ReadExceptionStatus,
     #[cfg(feature = "rtu")]
-    Diagnostics(SubFnCode, Data<'r>),
+    Diagnostics(DiagnosticSubFunction, Data<'r>),
     #[cfg(feature = "rtu")]
     GetCommEventCounter,
     #[cfg(feature = "rtu")]
@@ -145,14 +202,7 @@ pub enum Request<'r> {
     #[cfg(feature = "rtu")]
     ReportServerId,
     //TODO:
-    //- ReadFileRecord
-    //- WriteFileRecord
-    //- MaskWriteRegiger
-    //TODO:
-    //- Read FifoQueue
-    //- EncapsulatedInterfaceTransport
     //- CanOpenGeneralReferenceRequestAndResponsePdu
-    //- ReadDeviceIdentification
     Custom(FnCode, &'r [u8]),
 }
 
@@ -171,6 +221,22 @@ pub struct RequestPdu<'r>(pub Request<'r>);
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ResponsePdu<'r>(pub Result<Response<'r>, ExceptionResponse>);
 
+/// The header of a transport specific ADU.
+pub trait AduHeader: Copy {
+    /// The request ADU of the transport.
+    type RequestAdu<'r>;
+    /// The response ADU of the transport.
+    type ResponseAdu<'r>;
+    /// Build a request ADU with this header.
+    fn request_adu(self, pdu: RequestPdu<'_>) -> Self::RequestAdu<'_>;
+    /// Build a response ADU with this header.
+    fn response_adu(self, pdu: ResponsePdu<'_>) -> Self::ResponseAdu<'_>;
+    /// Returns `true` if the request is sent to all servers (slaves).
+    ///
+    /// Broadcast requests must not be answered.
+    fn is_broadcast(&self) -> bool;
+}
+
 #[cfg(feature = "rtu")]
 type Status = u16;
 #[cfg(feature = "rtu")]
@@ -190,10 +256,15 @@ pub enum Response<'r> {
     WriteSingleRegister(Address, Word),
     WriteMultipleRegisters(Address, Quantity),
     ReadWriteMultipleRegisters(Data<'r>),
+    MaskWriteRegister(Address, AndMask, OrMask),
+    ReadFifoQueue(Data<'r>),
+    ReadFileRecord(FileSubResponses<'r>),
+    WriteFileRecord(FileRecords<'r>),
+    ReadDeviceIdentification(DeviceIdentification<'r>),
     #[cfg(feature = "rtu")]
     ReadExceptionStatus(u8),
     #[cfg(feature = "rtu")]
-    Diagnostics(Data<'r>),
+    Diagnostics(DiagnosticSubFunction, Data<'r>),
     #[cfg(feature = "rtu")]
     GetCommEventCounter(Status, EventCount),
     #[cfg(feature = "rtu")]
@@ -201,14 +272,7 @@ pub enum Response<'r> {
     #[cfg(feature = "rtu")]
     ReportServerId(&'r [u8], bool),
     //TODO:
-    //- ReadFileRecord
-    //- WriteFileRecord
-    //- MaskWriteRegiger
-    //TODO:
-    //- Read FifoQueue
-    //- EncapsulatedInterfaceTransport
     //- CanOpenGeneralReferenceRequestAndResponsePdu
-    //- ReadDeviceIdentification
     Custom(FnCode, &'r [u8]),
 }
 
@@ -227,6 +291,11 @@ impl<'r> From<Request<'r>> for FnCode {
             WriteSingleRegister(_, _) => c::WriteSingleRegister,
             WriteMultipleRegisters(_, _) => c::WriteMultipleRegisters,
             ReadWriteMultipleRegisters(_, _, _, _) => c::ReadWriteMultipleRegisters,
+            MaskWriteRegister(_, _, _) => c::MaskWriteRegister,
+            ReadFifoQueue(_) => c::ReadFifoQueue,
+            ReadFileRecord(_) => c::ReadFileRecord,
+            WriteFileRecord(_) => c::WriteFileRecord,
+            ReadDeviceIdentification(_, _) => c::EncapsulatedInterfaceTransport,
             #[cfg(feature = "rtu")]
             ReadExceptionStatus => c::ReadExceptionStatus,
             #[cfg(feature = "rtu")]
@@ -257,10 +326,15 @@ impl<'r> From<Response<'r>> for FnCode {
             WriteSingleRegister(_, _) => c::WriteSingleRegister,
             WriteMultipleRegisters(_, _) => c::WriteMultipleRegisters,
             ReadWriteMultipleRegisters(_) => c::ReadWriteMultipleRegisters,
+            MaskWriteRegister(_, _, _) => c::MaskWriteRegister,
+            ReadFifoQueue(_) => c::ReadFifoQueue,
+            ReadFileRecord(_) => c::ReadFileRecord,
+            WriteFileRecord(_) => c::WriteFileRecord,
+            ReadDeviceIdentification(_) => c::EncapsulatedInterfaceTransport,
             #[cfg(feature = "rtu")]
             ReadExceptionStatus(_) => c::ReadExceptionStatus,
             #[cfg(feature = "rtu")]
-            Diagnostics(_) => c::Diagnostics,
+            Diagnostics(_, _) => c::Diagnostics,
             #[cfg(feature = "rtu")]
             GetCommEventCounter(_, _) => c::GetCommEventCounter,
             #[cfg(feature = "rtu")]
@@ -275,15 +349,57 @@ impl<'r> From<Response<'r>> for FnCode {
 /// A server (slave) exception.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Exception {
-    IllegalFunction = 0x01,
-    IllegalDataAddress = 0x02,
-    IllegalDataValue = 0x03,
-    ServerDeviceFailure = 0x04,
-    Acknowledge = 0x05,
-    ServerDeviceBusy = 0x06,
-    MemoryParityError = 0x08,
-    GatewayPathUnavailable = 0x0A,
-    GatewayTargetDevice = 0x0B,
+    IllegalFunction,
+    IllegalDataAddress,
+    IllegalDataValue,
+    ServerDeviceFailure,
+    Acknowledge,
+    ServerDeviceBusy,
+    MemoryParityError,
+    GatewayPathUnavailable,
+    GatewayTargetDevice,
+    /// An exception code that is not defined by the specification.
+    ///
+    /// Decoding never produces this variant for the codes of the named
+    /// variants. Don't construct it with such a code either: it encodes
+    /// to the same byte but won't compare equal to the named variant.
+    Other(u8),
+}
+
+impl From<u8> for Exception {
+    fn from(code: u8) -> Self {
+        use self::Exception::*;
+        match code {
+            0x01 => IllegalFunction,
+            0x02 => IllegalDataAddress,
+            0x03 => IllegalDataValue,
+            0x04 => ServerDeviceFailure,
+            0x05 => Acknowledge,
+            0x06 => ServerDeviceBusy,
+            0x08 => MemoryParityError,
+            0x0A => GatewayPathUnavailable,
+            0x0B => GatewayTargetDevice,
+            code => Other(code),
+        }
+    }
+}
+
+impl From<Exception> for u8 {
+    fn from(ex: Exception) -> Self {
+        use self::Exception::*;
+        match ex {
+            IllegalFunction => 0x01,
+            IllegalDataAddress => 0x02,
+            IllegalDataValue => 0x03,
+            ServerDeviceFailure => 0x04,
+            Acknowledge => 0x05,
+            ServerDeviceBusy => 0x06,
+            MemoryParityError => 0x08,
+            GatewayPathUnavailable => 0x0A,
+            GatewayTargetDevice => 0x0B,
+            Other(code) => code,
+        }
+    }
 }
 
 impl fmt::Display for Exception {
@@ -300,6 +416,7 @@ impl fmt::Display for Exception {
             MemoryParityError => "Memory parity error",
             GatewayPathUnavailable => "Gateway path unavailable",
             GatewayTargetDevice => "Gateway target device failed to respond",
+            Other(code) => return write!(f, "Exception code 0x{:0>2X}", code),
         };
         write!(f, "{}", desc)
     }
@@ -319,8 +436,16 @@ impl<'r> Request<'r> {
             WriteMultipleCoils(_, coils) => 6 + coils.packed_len(),
             WriteMultipleRegisters(_, words) => 6 + words.data.len(),
             ReadWriteMultipleRegisters(_, _, _, words) => 10 + words.data.len(),
+            MaskWriteRegister(_, _, _) => 7,
+            ReadFifoQueue(_) => 3,
+            ReadFileRecord(sub_requests) => 2 + sub_requests.data.len(),
+            WriteFileRecord(records) => 2 + records.data.len(),
+            ReadDeviceIdentification(_, _) => 4,
+            #[cfg(feature = "rtu")]
+            ReadExceptionStatus | GetCommEventCounter | GetCommEventLog | ReportServerId => 1,
+            #[cfg(feature = "rtu")]
+            Diagnostics(_, words) => 3 + words.data.len(),
             Custom(_, data) => 1 + data.len(),
-            _ => unimplemented!(), // TODO
         }
     }
 }
@@ -338,12 +463,33 @@ impl<'r> Response<'r> {
             ReadInputRegisters(words)
             | ReadHoldingRegisters(words)
             | ReadWriteMultipleRegisters(words) => 2 + words.data.len(),
+            MaskWriteRegister(_, _, _) => 7,
+            ReadFifoQueue(words) => 5 + words.data.len(),
+            ReadFileRecord(sub_responses) => 2 + sub_responses.data.len(),
+            WriteFileRecord(records) => 2 + records.data.len(),
+            ReadDeviceIdentification(dev_id) => 7 + dev_id.objects.len(),
+            #[cfg(feature = "rtu")]
+            ReadExceptionStatus(_) => 2,
+            #[cfg(feature = "rtu")]
+            Diagnostics(_, words) => 3 + words.data.len(),
+            #[cfg(feature = "rtu")]
+            GetCommEventCounter(_, _) => 5,
+            #[cfg(feature = "rtu")]
+            GetCommEventLog(_, _, _, events) => 8 + events.len(),
+            #[cfg(feature = "rtu")]
+            ReportServerId(server_id, _) => 3 + server_id.len(),
             Custom(_, data) => 1 + data.len(),
-            _ => unimplemented!(), // TODO
         }
     }
 }
 
+/// Apply the masks of a masked register write to the current register value.
+///
+/// `Result = (Current Contents AND And_Mask) OR (Or_Mask AND (NOT And_Mask))`
+pub const fn mask_register(current: Word, and_mask: AndMask, or_mask: OrMask) -> Word {
+    (current & and_mask) | (or_mask & !and_mask)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -363,6 +509,21 @@ mod tests {
         assert_eq!(FnCode::from(0xBB), FnCode::Custom(0xBB));
     }
 
+    #[test]
+    fn mei_type_into_u8() {
+        let x: u8 = MeiType::ReadDeviceIdentification.into();
+        assert_eq!(x, 0x0E);
+        let x: u8 = MeiType::Custom(0xBB).into();
+        assert_eq!(x, 0xBB);
+    }
+
+    #[test]
+    fn mei_type_from_u8() {
+        assert_eq!(MeiType::from(0x0D), MeiType::CanOpenGeneralReference);
+        assert_eq!(MeiType::from(0x0E), MeiType::ReadDeviceIdentification);
+        assert_eq!(MeiType::from(0xBB), MeiType::Custom(0xBB));
+    }
+
     #[test]
     fn function_code_from_request() {
         use Request::*;
@@ -405,6 +566,11 @@ mod tests {
                 ),
                 0x17,
             ),
+            (MaskWriteRegister(0, 0, 0), 0x16),
+            (ReadFifoQueue(0), 0x18),
+            (ReadFileRecord(FileSubRequests { data: &[] }), 0x14),
+            (WriteFileRecord(FileRecords { data: &[] }), 0x15),
+            (ReadDeviceIdentification(ReadDeviceIdCode::Basic, 0), 0x2B),
             (Custom(FnCode::Custom(88), &[]), 88),
         ];
         for (req, expected) in requests {
@@ -456,6 +622,27 @@ mod tests {
                 }),
                 0x17,
             ),
+            (MaskWriteRegister(0, 0, 0), 0x16),
+            (
+                ReadFifoQueue(Data {
+                    quantity: 0,
+                    data: &[],
+                }),
+                0x18,
+            ),
+            (ReadFileRecord(FileSubResponses { data: &[] }), 0x14),
+            (WriteFileRecord(FileRecords { data: &[] }), 0x15),
+            (
+                ReadDeviceIdentification(DeviceIdentification {
+                    read_device_id_code: ReadDeviceIdCode::Basic,
+                    conformity_level: 0x01,
+                    more_follows: false,
+                    next_object_id: 0,
+                    number_of_objects: 0,
+                    objects: &[],
+                }),
+                0x2B,
+            ),
             (Custom(FnCode::Custom(99), &[]), 99),
         ];
         for (req, expected) in responses {
@@ -474,6 +661,12 @@ mod tests {
                 .pdu_len(),
             7
         );
+        assert_eq!(Request::MaskWriteRegister(0x04, 0xF2, 0x25).pdu_len(), 7);
+        assert_eq!(Request::ReadFifoQueue(0x04DE).pdu_len(), 3);
+        assert_eq!(
+            Request::ReadDeviceIdentification(ReadDeviceIdCode::Basic, 0).pdu_len(),
+            4
+        );
         // TODO: extend test
     }
 
@@ -484,6 +677,19 @@ mod tests {
             Response::ReadCoils(Coils::from_bools(&[true], buf).unwrap()).pdu_len(),
             3
         );
+        assert_eq!(Response::MaskWriteRegister(0x04, 0xF2, 0x25).pdu_len(), 7);
+        let buf = &mut [0; 4];
+        assert_eq!(
+            Response::ReadFifoQueue(Data::from_words(&[0x01B8, 0x1284], buf).unwrap()).pdu_len(),
+            9
+        );
         // TODO: extend test
     }
+
+    #[test]
+    fn apply_register_mask() {
+        assert_eq!(mask_register(0x12, 0xF2, 0x25), 0x17);
+        assert_eq!(mask_register(0xABCD, 0xFFFF, 0x1234), 0xABCD);
+        assert_eq!(mask_register(0xABCD, 0x0000, 0x1234), 0x1234);
+    }
 }
@@ -9,6 +9,21 @@ pub struct Header {
     pub slave: SlaveId,
 }
 
+impl AduHeader for Header {
+    type RequestAdu<'r> = RequestAdu<'r>;
+    type ResponseAdu<'r> = ResponseAdu<'r>;
+
+    fn request_adu(self, pdu: RequestPdu<'_>) -> RequestAdu<'_> {
+        RequestAdu { hdr: self, pdu }
+    }
+    fn response_adu(self, pdu: ResponsePdu<'_>) -> ResponseAdu<'_> {
+        ResponseAdu { hdr: self, pdu }
+    }
+    fn is_broadcast(&self) -> bool {
+        self.slave == 0
+    }
+}
+
 /// RTU Request ADU
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RequestAdu<'r> {
@@ -9,6 +9,22 @@ pub struct Header {
     pub unit_id: UnitId,
 }
 
+impl AduHeader for Header {
+    type RequestAdu<'r> = RequestAdu<'r>;
+    type ResponseAdu<'r> = ResponseAdu<'r>;
+
+    fn request_adu(self, pdu: RequestPdu<'_>) -> RequestAdu<'_> {
+        RequestAdu { hdr: self, pdu }
+    }
+    fn response_adu(self, pdu: ResponsePdu<'_>) -> ResponseAdu<'_> {
+        ResponseAdu { hdr: self, pdu }
+    }
+    // There are no broadcasts on TCP.
+    fn is_broadcast(&self) -> bool {
+        false
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct RequestAdu<'r> {
     pub hdr: Header,
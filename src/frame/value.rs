@@ -0,0 +1,474 @@
+use super::*;
+use crate::error::*;
+use core::marker::PhantomData;
+
+/// The order of the bytes of a value that spans multiple registers.
+///
+/// The letters name the bytes of a value from the most to the least
+/// significant one (e.g. `0xAABBCCDD`) in the order they are transferred.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordOrder {
+    /// Big-endian
+    ABCD,
+    /// Big-endian with swapped words
+    CDAB,
+    /// Big-endian with swapped bytes within each word
+    BADC,
+    /// Little-endian
+    DCBA,
+}
+
+/// Convert between the transferred and the big-endian byte order.
+///
+/// Every reordering is its own inverse.
+fn reorder(bytes: &mut [u8], order: WordOrder) {
+    match order {
+        WordOrder::ABCD => {}
+        WordOrder::CDAB => {
+            let words = bytes.len() / 2;
+            for i in 0..words / 2 {
+                let j = words - 1 - i;
+                bytes.swap(2 * i, 2 * j);
+                bytes.swap(2 * i + 1, 2 * j + 1);
+            }
+        }
+        WordOrder::BADC => bytes.chunks_exact_mut(2).for_each(|w| w.swap(0, 1)),
+        WordOrder::DCBA => bytes.reverse(),
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// A value that is stored in consecutive registers.
+///
+/// The trait is sealed: it is implemented for `u32`, `i32`, `f32`,
+/// `u64`, `i64` and `f64` and cannot be implemented outside of this crate.
+pub trait RegisterValue: Copy + private::Sealed {
+    /// Number of registers the value occupies.
+    const WORDS: usize;
+    /// Read the value from big-endian bytes.
+    fn read_be(bytes: &[u8]) -> Self;
+    /// Write the value as big-endian bytes.
+    fn write_be(self, bytes: &mut [u8]);
+}
+
+macro_rules! register_value {
+    ($t:ty, $words:expr, $read:ident, $write:ident) => {
+        impl private::Sealed for $t {}
+
+        impl RegisterValue for $t {
+            const WORDS: usize = $words;
+
+            fn read_be(bytes: &[u8]) -> Self {
+                BigEndian::$read(bytes)
+            }
+            fn write_be(self, bytes: &mut [u8]) {
+                BigEndian::$write(bytes, self)
+            }
+        }
+    };
+}
+
+register_value!(u32, 2, read_u32, write_u32);
+register_value!(i32, 2, read_i32, write_i32);
+register_value!(f32, 2, read_f32, write_f32);
+register_value!(u64, 4, read_u64, write_u64);
+register_value!(i64, 4, read_i64, write_i64);
+register_value!(f64, 4, read_f64, write_f64);
+
+/// The maximum number of bytes of a [`RegisterValue`].
+const MAX_VALUE_LEN: usize = 8;
+
+impl<'d> Data<'d> {
+    /// Pack values into a byte buffer.
+    pub fn from_values<T: RegisterValue>(
+        values: &[T],
+        order: WordOrder,
+        target: &'d mut [u8],
+    ) -> Result<Self, Error> {
+        let len = T::WORDS * 2;
+        if values.len() * len > target.len() {
+            return Err(Error::BufferSize);
+        }
+        for (v, bytes) in values.iter().zip(target.chunks_exact_mut(len)) {
+            v.write_be(bytes);
+            reorder(bytes, order);
+        }
+        Ok(Data {
+            data: &target[..values.len() * len],
+            quantity: values.len() * T::WORDS,
+        })
+    }
+    /// Get a value that starts at the register with index `idx`.
+    pub fn get_value<T: RegisterValue>(&self, idx: usize, order: WordOrder) -> Option<T> {
+        if idx.checked_add(T::WORDS)? > self.quantity {
+            return None;
+        }
+        let len = T::WORDS * 2;
+        let bytes = &mut [0; MAX_VALUE_LEN][..len];
+        bytes.copy_from_slice(&self.data[idx * 2..idx * 2 + len]);
+        reorder(bytes, order);
+        Some(T::read_be(bytes))
+    }
+    /// Get an `u32` that starts at the register with index `idx`.
+    pub fn get_u32(&self, idx: usize, order: WordOrder) -> Option<u32> {
+        self.get_value(idx, order)
+    }
+    /// Get an `i32` that starts at the register with index `idx`.
+    pub fn get_i32(&self, idx: usize, order: WordOrder) -> Option<i32> {
+        self.get_value(idx, order)
+    }
+    /// Get an `f32` that starts at the register with index `idx`.
+    pub fn get_f32(&self, idx: usize, order: WordOrder) -> Option<f32> {
+        self.get_value(idx, order)
+    }
+    /// Get an `u64` that starts at the register with index `idx`.
+    pub fn get_u64(&self, idx: usize, order: WordOrder) -> Option<u64> {
+        self.get_value(idx, order)
+    }
+    /// Get an `i64` that starts at the register with index `idx`.
+    pub fn get_i64(&self, idx: usize, order: WordOrder) -> Option<i64> {
+        self.get_value(idx, order)
+    }
+    /// Get an `f64` that starts at the register with index `idx`.
+    pub fn get_f64(&self, idx: usize, order: WordOrder) -> Option<f64> {
+        self.get_value(idx, order)
+    }
+    /// Iterate over consecutive values.
+    ///
+    /// Remaining registers that do not form a complete value are skipped.
+    pub const fn values<T: RegisterValue>(&self, order: WordOrder) -> ValuesIter<'d, T> {
+        ValuesIter {
+            idx: 0,
+            order,
+            data: *self,
+            value: PhantomData,
+        }
+    }
+}
+
+impl<'d> DataMut<'d> {
+    /// Set a value that starts at the register with index `idx`.
+    pub fn set_value<T: RegisterValue>(
+        &mut self,
+        idx: usize,
+        value: T,
+        order: WordOrder,
+    ) -> Result<(), Error> {
+        match idx.checked_add(T::WORDS) {
+            Some(end) if end <= self.quantity => {}
+            _ => return Err(Error::BufferSize),
+        }
+        let bytes = &mut self.data[idx * 2..(idx + T::WORDS) * 2];
+        value.write_be(bytes);
+        reorder(bytes, order);
+        Ok(())
+    }
+}
+
+/// Characters that are trimmed from strings.
+const fn is_padding(c: u8) -> bool {
+    c == 0 || c == b' '
+}
+
+impl<'d> Data<'d> {
+    /// Pack an ASCII string with two characters per register.
+    ///
+    /// The first character is stored in the high byte of a register
+    /// unless `swap_bytes` is set. An odd length is padded with NUL.
+    pub fn from_ascii(s: &[u8], swap_bytes: bool, target: &'d mut [u8]) -> Result<Self, Error> {
+        let quantity = s.len().div_ceil(2);
+        if quantity * 2 > target.len() {
+            return Err(Error::BufferSize);
+        }
+        let target = &mut target[..quantity * 2];
+        target[..s.len()].copy_from_slice(s);
+        target[s.len()..].fill(0);
+        if swap_bytes {
+            reorder(target, WordOrder::BADC);
+        }
+        Ok(Data {
+            data: target,
+            quantity,
+        })
+    }
+    /// Copy an ASCII string with two characters per register into `target`.
+    ///
+    /// Leading and trailing NUL and space characters are trimmed.
+    pub fn copy_ascii<'t>(
+        &self,
+        swap_bytes: bool,
+        target: &'t mut [u8],
+    ) -> Result<&'t [u8], Error> {
+        let len = self.quantity * 2;
+        if len > target.len() {
+            return Err(Error::BufferSize);
+        }
+        let s = &mut target[..len];
+        s.copy_from_slice(&self.data[..len]);
+        if swap_bytes {
+            reorder(s, WordOrder::BADC);
+        }
+        let start = s.iter().position(|c| !is_padding(*c)).unwrap_or(len);
+        let end = s
+            .iter()
+            .rposition(|c| !is_padding(*c))
+            .map_or(start, |i| i + 1);
+        Ok(&target[start..end])
+    }
+    /// Pack 4-digit values as BCD with one value per register.
+    pub fn from_bcd4(values: &[u16], target: &'d mut [u8]) -> Result<Self, Error> {
+        if values.len() * 2 > target.len() {
+            return Err(Error::BufferSize);
+        }
+        for (v, bytes) in values.iter().zip(target.chunks_exact_mut(2)) {
+            let bcd = encode_bcd(u32::from(*v), 4)?;
+            BigEndian::write_u16(bytes, bcd as u16);
+        }
+        Ok(Data {
+            data: &target[..values.len() * 2],
+            quantity: values.len(),
+        })
+    }
+    /// Pack 8-digit values as BCD with one value per two registers.
+    pub fn from_bcd8(
+        values: &[u32],
+        order: WordOrder,
+        target: &'d mut [u8],
+    ) -> Result<Self, Error> {
+        if values.len() * 4 > target.len() {
+            return Err(Error::BufferSize);
+        }
+        for (v, bytes) in values.iter().zip(target.chunks_exact_mut(4)) {
+            encode_bcd(*v, 8)?.write_be(bytes);
+            reorder(bytes, order);
+        }
+        Ok(Data {
+            data: &target[..values.len() * 4],
+            quantity: values.len() * 2,
+        })
+    }
+    /// Get a 4-digit BCD value.
+    ///
+    /// It returns `None` if the register does not contain valid BCD digits.
+    pub fn get_bcd4(&self, idx: usize) -> Option<u16> {
+        self.get(idx)
+            .and_then(|bcd| decode_bcd(u32::from(bcd), 4))
+            .map(|v| v as u16)
+    }
+    /// Get an 8-digit BCD value that starts at the register with index `idx`.
+    ///
+    /// It returns `None` if the registers do not contain valid BCD digits.
+    pub fn get_bcd8(&self, idx: usize, order: WordOrder) -> Option<u32> {
+        self.get_u32(idx, order).and_then(|bcd| decode_bcd(bcd, 8))
+    }
+}
+
+fn decode_bcd(bcd: u32, digits: usize) -> Option<u32> {
+    (0..digits).rev().try_fold(0, |value, i| {
+        let digit = (bcd >> (i * 4)) & 0x0F;
+        (digit <= 9).then_some(value * 10 + digit)
+    })
+}
+
+fn encode_bcd(value: u32, digits: usize) -> Result<u32, Error> {
+    let mut rest = value;
+    let mut bcd = 0;
+    for i in 0..digits {
+        bcd |= (rest % 10) << (i * 4);
+        rest /= 10;
+    }
+    if rest != 0 {
+        return Err(Error::Bcd(value));
+    }
+    Ok(bcd)
+}
+
+/// Iterator over typed values of [`Data`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValuesIter<'d, T> {
+    idx: usize,
+    order: WordOrder,
+    data: Data<'d>,
+    value: PhantomData<T>,
+}
+
+impl<'d, T: RegisterValue> Iterator for ValuesIter<'d, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.data.get_value(self.idx, self.order)?;
+        self.idx += T::WORDS;
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use WordOrder::*;
+
+    const VALUE: u32 = 0xAABB_CCDD;
+
+    #[test]
+    fn read_u32_in_all_orders() {
+        let data = Data::new(&[0xAA, 0xBB, 0xCC, 0xDD], 2).unwrap();
+        assert_eq!(data.get_u32(0, ABCD), Some(VALUE));
+        let data = Data::new(&[0xCC, 0xDD, 0xAA, 0xBB], 2).unwrap();
+        assert_eq!(data.get_u32(0, CDAB), Some(VALUE));
+        let data = Data::new(&[0xBB, 0xAA, 0xDD, 0xCC], 2).unwrap();
+        assert_eq!(data.get_u32(0, BADC), Some(VALUE));
+        let data = Data::new(&[0xDD, 0xCC, 0xBB, 0xAA], 2).unwrap();
+        assert_eq!(data.get_u32(0, DCBA), Some(VALUE));
+        assert_eq!(data.get_u32(1, DCBA), None);
+        assert_eq!(data.get_u32(usize::MAX, DCBA), None);
+    }
+
+    #[test]
+    fn read_u64_in_all_orders() {
+        let value = 0x1122_3344_5566_7788;
+        let bytes = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88];
+        let data = Data::new(&bytes, 4).unwrap();
+        assert_eq!(data.get_u64(0, ABCD), Some(value));
+        let bytes = [0x77, 0x88, 0x55, 0x66, 0x33, 0x44, 0x11, 0x22];
+        let data = Data::new(&bytes, 4).unwrap();
+        assert_eq!(data.get_u64(0, CDAB), Some(value));
+        let bytes = [0x22, 0x11, 0x44, 0x33, 0x66, 0x55, 0x88, 0x77];
+        let data = Data::new(&bytes, 4).unwrap();
+        assert_eq!(data.get_u64(0, BADC), Some(value));
+        let bytes = [0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11];
+        let data = Data::new(&bytes, 4).unwrap();
+        assert_eq!(data.get_u64(0, DCBA), Some(value));
+        assert_eq!(data.get_u64(1, DCBA), None);
+    }
+
+    #[test]
+    fn read_signed_and_floats() {
+        let data = Data::new(&[0xFF, 0xFF, 0xFF, 0xFE, 0x3F, 0xC0, 0x00, 0x00], 4).unwrap();
+        assert_eq!(data.get_i32(0, ABCD), Some(-2));
+        assert_eq!(data.get_f32(2, ABCD), Some(1.5));
+        assert_eq!(data.get_i64(0, ABCD), Some(-0x1_C040_0000));
+        let data = Data::new(&[0, 0, 0, 0, 0, 0, 0xF8, 0x3F], 4).unwrap();
+        assert_eq!(data.get_f64(0, DCBA), Some(1.5));
+    }
+
+    #[test]
+    fn write_and_read_values() {
+        for order in [ABCD, CDAB, BADC, DCBA] {
+            let buf = &mut [0; 10];
+            let data = Data::from_values(&[-1.25_f32, 3.0], order, buf).unwrap();
+            assert_eq!(data.len(), 4);
+            assert_eq!(data.data.len(), 8);
+            assert_eq!(data.get_f32(0, order), Some(-1.25));
+            assert_eq!(data.get_f32(2, order), Some(3.0));
+
+            let buf = &mut [0; 8];
+            let data = Data::from_values(&[i64::MIN + 7], order, buf).unwrap();
+            assert_eq!(data.get_i64(0, order), Some(i64::MIN + 7));
+        }
+        let buf = &mut [0; 4];
+        let data = Data::from_values(&[VALUE], CDAB, buf).unwrap();
+        assert_eq!(data.get(0), Some(0xCCDD));
+        assert_eq!(data.get(1), Some(0xAABB));
+        let buf = &mut [0; 7];
+        assert_eq!(
+            Data::from_values(&[0_u32, 0], ABCD, buf),
+            Err(Error::BufferSize)
+        );
+    }
+
+    #[test]
+    fn read_ascii() {
+        let data = Data::new(b"SN-12345\0\0", 5).unwrap();
+        let buf = &mut [0; 10];
+        assert_eq!(data.copy_ascii(false, buf).unwrap(), b"SN-12345");
+        let data = Data::new(b"NS1-3254  ", 5).unwrap();
+        let buf = &mut [0; 10];
+        assert_eq!(data.copy_ascii(true, buf).unwrap(), b"SN-12345");
+        let data = Data::new(b" \0\0 ", 2).unwrap();
+        let buf = &mut [0; 4];
+        assert_eq!(data.copy_ascii(false, buf).unwrap(), b"");
+        let buf = &mut [0; 3];
+        assert_eq!(data.copy_ascii(false, buf), Err(Error::BufferSize));
+    }
+
+    #[test]
+    fn write_ascii() {
+        let buf = &mut [0xFF; 8];
+        let data = Data::from_ascii(b"V1.2.3", false, buf).unwrap();
+        assert_eq!(data.len(), 3);
+        assert_eq!(data.get(0), Some(0x5631));
+        let buf = &mut [0xFF; 8];
+        let data = Data::from_ascii(b"V1.", true, buf).unwrap();
+        assert_eq!(data.data, b"1V\0.");
+        let buf = &mut [0; 4];
+        assert_eq!(data.copy_ascii(true, buf).unwrap(), b"V1.");
+        let buf = &mut [0; 2];
+        assert_eq!(Data::from_ascii(b"V1.", false, buf), Err(Error::BufferSize));
+    }
+
+    #[test]
+    fn read_bcd() {
+        let data = Data::new(&[0x12, 0x34, 0x56, 0x78, 0x00, 0x9A], 3).unwrap();
+        assert_eq!(data.get_bcd4(0), Some(1234));
+        assert_eq!(data.get_bcd4(2), None);
+        assert_eq!(data.get_bcd4(3), None);
+        assert_eq!(data.get_bcd8(0, ABCD), Some(1234_5678));
+        assert_eq!(data.get_bcd8(0, CDAB), Some(5678_1234));
+        assert_eq!(data.get_bcd8(1, ABCD), None);
+    }
+
+    #[test]
+    fn write_bcd() {
+        let buf = &mut [0; 4];
+        let data = Data::from_bcd4(&[9999, 42], buf).unwrap();
+        assert_eq!(data.get(0), Some(0x9999));
+        assert_eq!(data.get(1), Some(0x0042));
+        assert_eq!(Data::from_bcd4(&[10_000], buf), Err(Error::Bcd(10_000)));
+
+        let buf = &mut [0; 4];
+        let data = Data::from_bcd8(&[9876_5432], CDAB, buf).unwrap();
+        assert_eq!(data.get(0), Some(0x5432));
+        assert_eq!(data.get(1), Some(0x9876));
+        assert_eq!(data.get_bcd8(0, CDAB), Some(9876_5432));
+        assert_eq!(
+            Data::from_bcd8(&[1_0000_0000], ABCD, buf),
+            Err(Error::Bcd(1_0000_0000))
+        );
+    }
+
+    #[test]
+    fn set_values_in_place() {
+        let buf = &mut [0; 12];
+        let mut data = DataMut::new(buf, 6).unwrap();
+        data.set(0, 0x0102).unwrap();
+        data.set_value(1, -1.5_f32, CDAB).unwrap();
+        data.set_value(3, VALUE, DCBA).unwrap();
+        assert_eq!(data.set_value(5, VALUE, DCBA), Err(Error::BufferSize));
+        assert_eq!(
+            data.set_value(usize::MAX, 0_u64, DCBA),
+            Err(Error::BufferSize)
+        );
+        let data = data.into_data();
+        assert_eq!(data.get_f32(1, CDAB), Some(-1.5));
+        assert_eq!(data.get_u32(3, DCBA), Some(VALUE));
+        assert_eq!(data.get(0), Some(0x0102));
+        assert_eq!(data.get(5), Some(0));
+    }
+
+    #[test]
+    fn iterate_over_values() {
+        let buf = &mut [0; 12];
+        let data = Data::from_values(&[1_u32, 2, 3], BADC, buf).unwrap();
+        let mut iter = data.values::<u32>(BADC);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), Some(3));
+        assert_eq!(iter.next(), None);
+        assert_eq!(data.values::<u64>(BADC).count(), 1);
+        assert_eq!(data.values::<f32>(BADC).count(), 3);
+    }
+}
@@ -1,5 +1,8 @@
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 #[macro_use]
 extern crate log;
 
@@ -7,7 +10,13 @@ mod codec;
 mod error;
 mod frame;
 
+#[cfg(feature = "ascii")]
+pub use codec::ascii;
 pub use codec::rtu;
+pub use codec::rtu_over_tcp;
 pub use codec::tcp;
+#[cfg(feature = "udp")]
+pub use codec::udp;
+pub use codec::{DataStore, RequestError, Service, Table};
 pub use error::*;
 pub use frame::*;